@@ -20,12 +20,19 @@ struct Cli {
 static COMMANDS: &[(&str, &str)] = &[
     ("help",             "Displays this the help page"),
     ("join_game",        "Set the game to make moves against, usage: join_game <game_address>"),
-    ("new_game",         "Create a new game to play with an opponent, usage: new_game <opponent_address>"),
-    ("moves",            "Display the set of moves this game supports"),
+    ("games",            "List the games you are playing and have played"),
+    ("new_game",         "Create a new game to play with an opponent, usage: new_game <opponent_address> [checkers] [<width>x<height>] [connect<N>] [popout] [<seconds>s | <total>+<increment>]"),
+    ("moves",            "Display the moves you can make in the current game, or examples of every move if no game is set"),
     ("make_move",        "Make a move in this game, usage: make_move <move_json>"),
+    ("resign",           "Resign from the current game. Your opponent wins."),
+    ("offer_draw",       "Offer your opponent a draw in the current game"),
+    ("accept_draw",      "Accept the draw your opponent has offered. The game ends as a draw."),
+    ("decline_draw",     "Decline the draw your opponent has offered"),
+    ("claim_timeout",    "Claim the win when your opponent has run out of time on their clock"),
+    ("replay",           "Step through the moves of the current game, usage: replay [<move_number>]. Then enter or \"n\" steps forward, \"p\" steps back, a number jumps to that move and \"q\" stops."),
  
     ("create_proposal",  "Publicly publish that you are looking for someone to play with. Usage: post_propoal <message>"),
-    ("accept_proposal",   "Accept a propsal. This will start a new game. Usage: accept_proposal <proposal_hash>"),
+    ("accept_proposal",   "Accept a propsal. This will start a new game. Usage: accept_proposal <proposal_hash> [checkers] [<width>x<height>] [connect<N>] [popout] [<seconds>s | <total>+<increment>]"),
     ("get_proposals",    "Get all of the public proposals that are current"),
     ("check_responses",  "Given a proposal hash find the responses. Usage: check_responses <proposal_hash>"),
     ("remove_proposal",  "Remove a proposal that you authored given its hash. Usage: remove_proposal <proposal_hash>"),    
@@ -39,9 +46,13 @@ fn main() -> io::Result<()> {
     // create the functions required for playing the game
     let whoami = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "whoami".into());
     let valid_moves = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "get_valid_moves".into());
+    let legal_moves = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "get_legal_moves".into());
     let make_move = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "make_move".into());
     let create_game = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "create_game".into());
     let render_game = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "render_state".into());
+    let render_game_at = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "render_state_at".into());
+    let my_games = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "get_my_games".into());
+    let game_summary = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "get_game_summary".into());
 
     // matchmaking funcs
     let create_proposal = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "create_proposal".into());
@@ -109,13 +120,37 @@ fn main() -> io::Result<()> {
             		Err("argument must be a valid address".into())
             	}
             }
+            "games" => {
+                whoami(json!({})).and_then(|agent_addr| {
+                    my_games(json!({})).map(|games| {
+                        for (heading, list) in &[("Active games", &games["active"]), ("Finished games", &games["finished"])] {
+                            println!("{}: \n", heading);
+                            list.as_array().unwrap().iter().for_each(|game| {
+                                let summary = &game["entry"];
+                                let opponent = if summary["player_1"] == agent_addr { &summary["player_2"] } else { &summary["player_1"] };
+                                let status = match summary["status"]["outcome"].as_str() {
+                                    Some(outcome) => outcome.to_string(),
+                                    None if summary["current_player_address"] == agent_addr => "Your turn".to_string(),
+                                    None => "Opponent's turn".to_string(),
+                                };
+                                println!("[{}] : {{ Opponent: {}, Moves: {}, {} }}", game["address"].as_str().unwrap(), opponent, summary["move_count"], status);
+                            });
+                            println!();
+                        }
+                        println!("use \"join_game\" with any of the listed addresses to resume or review it: \n");
+                    })
+                })
+            }
             "new_game" => {
-            	if is_agent_addr(args) {
-            		let result = create_game(json!({
-            			"opponent": args,
-            			"timestamp": current_timestamp()
-            		}));
-                    result.map(|result| {
+                let (opponent, options) = split_first_word(args);
+            	if is_agent_addr(opponent) {
+                    parse_game_options(options).and_then(|options| {
+                        create_game(json!({
+                            "opponent": opponent,
+                            "timestamp": current_timestamp(),
+                            "options": options
+                        }))
+                    }).map(|result| {
                         current_game = result.as_str().map(|s| s.to_string());
                     })
             	} else {
@@ -123,15 +158,27 @@ fn main() -> io::Result<()> {
             	}
             }
             "moves" => {
-            	valid_moves(json!({})).map(|result| {
-	            	println!("The valid moves are:");
-	            	result.as_array().unwrap()
-	            	.iter()
-	            	.for_each(|elem| {
-	            		println!("- {}", elem);
-	            	});
-                    println!();
-            	})
+                if let Some(current_game) = current_game.clone() {
+                    legal_moves(json!({"game_address": current_game, "player": null, "timestamp": current_timestamp()})).map(|result| {
+                        println!("The moves you can make now are:");
+                        result["moves"].as_array().unwrap()
+                        .iter()
+                        .for_each(|elem| {
+                            println!("- {}", elem);
+                        });
+                        println!();
+                    })
+                } else {
+                    valid_moves(json!({"game_address": null})).map(|result| {
+                        println!("The valid moves are:");
+                        result.as_array().unwrap()
+                        .iter()
+                        .for_each(|elem| {
+                            println!("- {}", elem);
+                        });
+                        println!();
+                    })
+                }
             },
             "make_move" => {
             	if let Some(current_game) = current_game.clone() {
@@ -155,6 +202,65 @@ fn main() -> io::Result<()> {
             		Err("No game set to make moves on. use the \"join_game\" command.".into())
             	}
             },
+            "resign" | "offer_draw" | "accept_draw" | "decline_draw" | "claim_timeout" => {
+            	if let Some(current_game) = current_game.clone() {
+                    let move_type = match cmd {
+                        "resign" => "Resign",
+                        "offer_draw" => "OfferDraw",
+                        "accept_draw" => "AcceptDraw",
+                        "claim_timeout" => "ClaimTimeout",
+                        _ => "DeclineDraw",
+                    };
+	            	make_move(json!({
+		            	"new_move": {
+		            		"game": current_game,
+		            		"move_type": move_type,
+		            		"timestamp": current_timestamp()
+		            	}
+	            	})).map(|_| {
+                        println!("{} cast successfully", move_type);
+                    })
+                }
+            	else {
+            		Err("No game set to make moves on. use the \"join_game\" command.".into())
+            	}
+            },
+            "replay" => {
+                if let Some(current_game) = current_game.clone() {
+                    game_summary(json!({"game_address": current_game})).and_then(|summary| {
+                        let move_count = summary["move_count"].as_u64().unwrap_or(0);
+                        let mut ply = if args.is_empty() {
+                            0
+                        } else {
+                            args.parse::<u64>().map_err(|_| format!("Invalid move number: {}", args))?.min(move_count)
+                        };
+                        interface.set_prompt("replay> ").map_err(|e| e.to_string())?;
+                        loop {
+                            let render_result = render_game_at(json!({"game_address": current_game, "ply": ply}))?;
+                            println!("{}", render_result.as_str().unwrap());
+                            println!("Position after move {} of {}", ply, move_count);
+
+                            let line = match interface.read_line().map_err(|e| e.to_string())? {
+                                ReadResult::Input(line) => line,
+                                _ => break,
+                            };
+                            match line.trim() {
+                                "" | "n" => ply = (ply + 1).min(move_count),
+                                "p" => ply = ply.saturating_sub(1),
+                                "q" => break,
+                                jump => match jump.parse::<u64>() {
+                                    Ok(jump) => ply = jump.min(move_count),
+                                    Err(_) => println!("Enter \"n\", \"p\", a move number or \"q\""),
+                                },
+                            }
+                        }
+                        Ok(())
+                    })
+                }
+                else {
+                    Err("No game set to replay. use the \"join_game\" command.".into())
+                }
+            },
             "create_proposal" => {
                 println!("creating proposal with message {:?}", args);
                 let result = create_proposal(json!({"message": args}));
@@ -171,7 +277,10 @@ fn main() -> io::Result<()> {
                 Ok(())
             },
             "accept_proposal" => {
-                accept_proposal(json!({"proposal_addr": args, "created_at": current_timestamp()})).map(|game_addr| {
+                let (proposal_addr, options) = split_first_word(args);
+                parse_game_options(options).and_then(|options| {
+                    accept_proposal(json!({"proposal_addr": proposal_addr, "created_at": current_timestamp(), "options": options}))
+                }).map(|game_addr| {
                     println!("Proposal accepted. Game created with address: {}", game_addr);
                     current_game = Some(game_addr.as_str().unwrap().into());
                 })
@@ -295,6 +404,51 @@ fn is_agent_addr(s: &str) -> bool {
 	s.starts_with("Hc") && s.len() == 63
 }
 
+/**
+ * Parses the optional game settings following new_game or accept_proposal, e.g. "8x7 connect5 popout" or "checkers"
+ */
+fn parse_game_options(s: &str) -> Result<serde_json::Value, String> {
+    let mut options = json!({});
+    for option in s.split_whitespace() {
+        if option == "popout" {
+            options["variant"] = json!("PopOut");
+            continue;
+        }
+        if option == "checkers" || option == "connect_four" {
+            options["game_type"] = json!(option);
+            continue;
+        }
+        if option.ends_with('s') {
+            let seconds = &option[..option.len() - 1];
+            options["time_control"] = json!({"PerMove": {
+                "seconds": seconds.parse::<u32>().map_err(|_| format!("Invalid time per move: {}", seconds))?
+            }});
+            continue;
+        }
+        if let [total, increment] = option.split('+').collect::<Vec<&str>>().as_slice() {
+            options["time_control"] = json!({"Fischer": {
+                "total": total.parse::<u32>().map_err(|_| format!("Invalid total time: {}", total))?,
+                "increment": increment.parse::<u32>().map_err(|_| format!("Invalid time increment: {}", increment))?
+            }});
+            continue;
+        }
+        if option.starts_with("connect") {
+            let win_length = &option["connect".len()..];
+            options["win_length"] = json!(win_length.parse::<u32>().map_err(|_| format!("Invalid win length: {}", win_length))?);
+            continue;
+        }
+        let dimensions: Vec<&str> = option.split('x').collect();
+        match dimensions.as_slice() {
+            [width, height] => {
+                options["board_width"] = json!(width.parse::<u32>().map_err(|_| format!("Invalid board width: {}", width))?);
+                options["board_height"] = json!(height.parse::<u32>().map_err(|_| format!("Invalid board height: {}", height))?);
+            },
+            _ => return Err(format!("Unrecognised game option: {}", option)),
+        }
+    }
+    Ok(options)
+}
+
 fn current_timestamp() -> u32 {
 	SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as u32
 }
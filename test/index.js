@@ -18,10 +18,35 @@ const dnaPath = path.join(
 );
 const dna = Diorama.dna(dnaPath, "distributed-connect-four");
 
+// Timestamps must be close to the time moves are committed
+const now = Math.floor(Date.now() / 1000);
+
+// Timed games need timestamps much closer to the commit time, so they use the current time of each call
+const currentTime = () => Math.floor(Date.now() / 1000);
+
+const sleep = ms => new Promise(resolve => setTimeout(resolve, ms));
+
+// Makes each move in turn, starting with Bob who moves first, and returns the results
+const playInTurn = async (game, { alice, bob }, move_types) => {
+  const results = [];
+  for (const [i, move_type] of move_types.entries()) {
+    const player = i % 2 === 0 ? bob : alice;
+    results.push(
+      await player.callSync("main", "make_move", {
+        new_move: { game, move_type, timestamp: currentTime() }
+      })
+    );
+  }
+  return results;
+};
+
+const drop = column => ({ DropPiece: { column } });
+
 const diorama = new Diorama({
   instances: {
     alice: dna,
-    bob: dna
+    bob: dna,
+    carol: dna
   },
   bridges: [],
   debugLog: false,
@@ -34,11 +59,33 @@ diorama.registerScenario(
   async (s, t, { alice, bob }) => {
     const create_game_result = await alice.callSync("main", "create_game", {
       opponent: bob.agentId,
-      timestamp: 0
+      timestamp: now
     });
     console.log(create_game_result);
     t.equal(create_game_result.Ok.length, 46, "Alice can create a game");
 
+    const backdated_move = await bob.callSync("main", "make_move", {
+      new_move: {
+        game: create_game_result.Ok,
+        move_type: { DropPiece: { column: 0 } },
+        timestamp: now - 10
+      }
+    });
+    t.equal(
+      backdated_move.Ok,
+      undefined,
+      "Moves cannot be dated before the game was created"
+    );
+
+    const future_move = await bob.callSync("main", "make_move", {
+      new_move: {
+        game: create_game_result.Ok,
+        move_type: { DropPiece: { column: 0 } },
+        timestamp: now + 600
+      }
+    });
+    t.equal(future_move.Ok, undefined, "Moves cannot be dated in the future");
+
     const move_1_result = await bob.callSync("main", "make_move", {
       new_move: {
         game: create_game_result.Ok,
@@ -47,7 +94,7 @@ diorama.registerScenario(
             column: 0
           }
         },
-        timestamp: 1
+        timestamp: now + 1
       }
     });
     console.log(move_1_result);
@@ -57,6 +104,48 @@ diorama.registerScenario(
       "Bob can make first move in first column"
     );
 
+    // make_move always sets the author to the caller, so Bob commits a move Alice could make naming Alice as its author
+    const moves_so_far = await bob.callSync("main", "get_state_since", {
+      game_address: create_game_result.Ok,
+      since: { Ply: 0 }
+    });
+    const forged_move = await bob.callSync("main", "commit_move", {
+      new_move: {
+        game: create_game_result.Ok,
+        author: alice.agentId,
+        move_type: { DropPiece: { column: 1 } },
+        previous_move: moves_so_far.Ok.addresses[0],
+        timestamp: now + 2
+      }
+    });
+    t.equal(forged_move.Ok, undefined, "Bob cannot make a move on Alice's behalf");
+    t.ok(
+      JSON.stringify(forged_move.Err).includes("on behalf of another agent"),
+      "The move is rejected because Bob is not its author"
+    );
+
+    const state_after_move_1 = await alice.callSync("main", "get_state", {
+      game_address: create_game_result.Ok
+    });
+    t.equal(
+      state_after_move_1.Ok.moves_history[0].author,
+      bob.agentId,
+      "Moves are authored by the agent that made them"
+    );
+
+    const legal_moves = await alice.callSync("main", "get_legal_moves", {
+      game_address: create_game_result.Ok,
+      player: null,
+      timestamp: now + 2
+    });
+    t.equal(legal_moves.Ok.current_player, 1, "It is Alice's turn");
+    t.deepEqual(
+      legal_moves.Ok.moves.filter(m => m.DropPiece).map(m => m.DropPiece.column),
+      [0, 1, 2, 3, 4, 5, 6],
+      "Alice can drop a piece in any column"
+    );
+    t.ok(legal_moves.Ok.moves.includes("Resign"), "Alice can resign");
+
     const invalid_move = await alice.callSync("main", "make_move", {
       new_move: {
         game: create_game_result.Ok,
@@ -65,7 +154,7 @@ diorama.registerScenario(
             column: 99
           }
         },
-        timestamp: 2
+        timestamp: now + 2
       }
     });
     console.log(invalid_move);
@@ -74,6 +163,11 @@ diorama.registerScenario(
       undefined,
       "Correctly invalidates out of bounds DropPiece"
     );
+    t.deepEqual(
+      JSON.parse(invalid_move.Err.Internal),
+      { ColumnOutOfBounds: { column: 99 } },
+      "Invalid moves are reported as structured errors"
+    );
 
     const move_2_result = await alice.callSync("main", "make_move", {
       new_move: {
@@ -83,7 +177,7 @@ diorama.registerScenario(
             column: 0
           }
         },
-        timestamp: 3
+        timestamp: now + 3
       }
     });
     console.log(move_2_result);
@@ -92,6 +186,662 @@ diorama.registerScenario(
       undefined,
       "Alice can make her first move after Bob moved"
     );
+
+    const bob_view = await bob.callSync("main", "render_state", {
+      game_address: create_game_result.Ok
+    });
+    t.ok(
+      bob_view.Ok.includes("It is your turn (Player 2)"),
+      "Bob is told it is his turn"
+    );
+
+    const spectator_view = await alice.callSync("main", "render_state_for", {
+      game_address: create_game_result.Ok,
+      viewer: null
+    });
+    t.ok(
+      spectator_view.Ok.includes("You are spectating"),
+      "Games can be rendered for spectators"
+    );
+    t.ok(
+      spectator_view.Ok.includes("It is Player 2's turn"),
+      "Spectators are told whose turn it is by player number"
+    );
+
+    const forks = await bob.callSync("main", "get_forks", {
+      game_address: create_game_result.Ok
+    });
+    t.deepEqual(forks.Ok, [], "A game played in turn has no forks");
+
+    const my_games = await alice.callSync("main", "get_my_games", {});
+    t.deepEqual(
+      my_games.Ok.active.map(game => game.address),
+      [create_game_result.Ok],
+      "New games are listed as active for their creator"
+    );
+
+    const since_start = await alice.callSync("main", "get_state_since", {
+      game_address: create_game_result.Ok,
+      since: { Ply: 0 }
+    });
+    t.equal(since_start.Ok.moves.length, 2, "Polling from the start returns every move");
+    t.deepEqual(
+      since_start.Ok.status,
+      { in_progress: true, current_player: 2, winner: null, outcome: null },
+      "Polling returns whose turn it is"
+    );
+
+    const since_latest = await alice.callSync("main", "get_state_since", {
+      game_address: create_game_result.Ok,
+      since: { LastMove: since_start.Ok.last_move }
+    });
+    t.equal(since_latest.Ok.moves.length, 0, "No moves have been made since the latest move");
+    t.equal(since_latest.Ok.ply, 2, "Polling returns how many moves have been made");
+
+    const summary = await bob.callSync("main", "get_game_summary", {
+      game_address: create_game_result.Ok
+    });
+    t.equal(summary.Ok.first_player, bob.agentId, "Bob moved first");
+    t.equal(summary.Ok.move_count, 2, "The summary counts the moves made");
+    t.equal(summary.Ok.current_player_address, bob.agentId, "The summary says whose turn it is");
+    t.equal(summary.Ok.last_move_at, now + 3, "The summary has the time of the latest move");
+
+    const state_after_first_move = await alice.callSync("main", "get_state_at", {
+      game_address: create_game_result.Ok,
+      ply: 1
+    });
+    t.equal(
+      state_after_first_move.Ok.moves_history.length,
+      1,
+      "Can look up the state after an earlier move"
+    );
+
+    const state_after_future_move = await alice.callSync("main", "get_state_at", {
+      game_address: create_game_result.Ok,
+      ply: 3
+    });
+    t.deepEqual(
+      JSON.parse(state_after_future_move.Err.Internal),
+      { PlyOutOfRange: { ply: 3, move_count: 2 } },
+      "Cannot look up moves that have not been made"
+    );
+  }
+);
+
+diorama.registerScenario(
+  "Can resign from a game at any time",
+  async (s, t, { alice, bob }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: now
+    });
+    t.equal(create_game_result.Ok.length, 46, "Alice can create a game");
+
+    // Bob moves first, so Alice resigning here is out of turn
+    const resign_result = await alice.callSync("main", "make_move", {
+      new_move: {
+        game: create_game_result.Ok,
+        move_type: "Resign",
+        timestamp: now + 1
+      }
+    });
+    console.log(resign_result);
+    t.equal(resign_result.Err, undefined, "Alice can resign before her turn");
+
+    const state = await bob.callSync("main", "get_state", {
+      game_address: create_game_result.Ok
+    });
+    t.equal(state.Ok.in_progress, false, "Game has ended");
+    t.equal(state.Ok.player_1.resigned, true, "Alice is marked as resigned");
+    t.deepEqual(
+      state.Ok.outcome,
+      { Resigned: { winner: 2 } },
+      "Bob wins by resignation"
+    );
+
+    const move_after_resign = await bob.callSync("main", "make_move", {
+      new_move: {
+        game: create_game_result.Ok,
+        move_type: {
+          DropPiece: {
+            column: 0
+          }
+        },
+        timestamp: now + 2
+      }
+    });
+    t.equal(
+      move_after_resign.Ok,
+      undefined,
+      "No moves can be made after a resignation"
+    );
+
+    const bobs_games = await alice.callSync("main", "get_games_of", {
+      agent: bob.agentId
+    });
+    t.deepEqual(
+      bobs_games.Ok.finished.map(game => game.address),
+      [create_game_result.Ok],
+      "Resigned games are listed as finished for both players"
+    );
+    t.deepEqual(bobs_games.Ok.active, [], "Finished games are no longer active");
+  }
+);
+
+diorama.registerScenario(
+  "Only the opponent can accept a draw offer",
+  async (s, t, { alice, bob }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: now
+    });
+    const game = create_game_result.Ok;
+
+    const offer_result = await bob.callSync("main", "make_move", {
+      new_move: { game, move_type: "OfferDraw", timestamp: currentTime() }
+    });
+    t.equal(offer_result.Err, undefined, "Bob can offer a draw");
+
+    const self_accept = await bob.callSync("main", "make_move", {
+      new_move: { game, move_type: "AcceptDraw", timestamp: currentTime() }
+    });
+    t.equal(self_accept.Ok, undefined, "Bob cannot accept his own offer");
+
+    const decline_result = await alice.callSync("main", "make_move", {
+      new_move: { game, move_type: "DeclineDraw", timestamp: currentTime() }
+    });
+    t.equal(decline_result.Err, undefined, "Alice can decline the offer");
+
+    const repeated_offer = await bob.callSync("main", "make_move", {
+      new_move: { game, move_type: "OfferDraw", timestamp: currentTime() }
+    });
+    t.equal(repeated_offer.Ok, undefined, "Bob cannot offer again straight after the offer is declined");
+
+    await bob.callSync("main", "make_move", {
+      new_move: { game, move_type: { DropPiece: { column: 0 } }, timestamp: currentTime() }
+    });
+    const later_offer = await bob.callSync("main", "make_move", {
+      new_move: { game, move_type: "OfferDraw", timestamp: currentTime() }
+    });
+    t.equal(later_offer.Err, undefined, "Bob can offer again once a turn has been played");
+
+    const accept_result = await alice.callSync("main", "make_move", {
+      new_move: { game, move_type: "AcceptDraw", timestamp: currentTime() }
+    });
+    t.equal(accept_result.Err, undefined, "Alice can accept the offer");
+
+    const state = await bob.callSync("main", "get_state", {
+      game_address: game
+    });
+    t.equal(state.Ok.in_progress, false, "Game has ended in a draw");
+    t.equal(state.Ok.outcome, "DrawAgreed", "Outcome records the agreed draw");
+  }
+);
+
+diorama.registerScenario(
+  "Can play checkers in the same DNA",
+  async (s, t, { alice, bob }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: now,
+      options: { game_type: "checkers" }
+    });
+    t.equal(create_game_result.Ok.length, 46, "Alice can create a checkers game");
+    const game = create_game_result.Ok;
+
+    const popout_checkers = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: now,
+      options: { game_type: "checkers", variant: "PopOut" }
+    });
+    t.equal(popout_checkers.Ok, undefined, "Checkers games cannot use Connect Four options");
+
+    const large_checkers = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: now,
+      options: { game_type: "checkers", board_width: 10, board_height: 10 }
+    });
+    t.equal(large_checkers.Ok, undefined, "Checkers is only played on an 8x8 board");
+
+    const straight_move = await bob.callSync("main", "make_move", {
+      new_move: {
+        game,
+        move_type: { MovePiece: { from: { x: 2, y: 2 }, to: { x: 2, y: 3 } } },
+        timestamp: now + 1
+      }
+    });
+    t.equal(straight_move.Ok, undefined, "Pieces can only move diagonally");
+
+    const move_result = await bob.callSync("main", "make_move", {
+      new_move: {
+        game,
+        move_type: { MovePiece: { from: { x: 2, y: 2 }, to: { x: 3, y: 3 } } },
+        timestamp: now + 2
+      }
+    });
+    t.equal(move_result.Err, undefined, "Bob can move a piece forwards");
+  }
+);
+
+diorama.registerScenario(
+  "Checkers captures are compulsory and must be completed",
+  async (s, t, { alice, bob }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: now,
+      options: { game_type: "checkers" }
+    });
+    const game = create_game_result.Ok;
+    const step = (from_x, from_y, to_x, to_y) => ({
+      MovePiece: { from: { x: from_x, y: from_y }, to: { x: to_x, y: to_y } }
+    });
+
+    // Bob leaves a piece on (2, 4) that Alice can jump, then on to (1, 1) over the piece on (2, 2)
+    const opening = await playInTurn(game, { alice, bob }, [
+      step(0, 2, 1, 3), step(1, 5, 0, 4), step(1, 1, 0, 2), step(0, 6, 1, 5), step(1, 3, 2, 4)
+    ]);
+    t.ok(opening.every(result => result.Err === undefined), "The opening moves are made");
+
+    const alice_move = move_type =>
+      alice.callSync("main", "make_move", {
+        new_move: { game, move_type, timestamp: currentTime() }
+      });
+
+    const plain_step = await alice_move(step(5, 5, 4, 4));
+    t.equal(plain_step.Ok, undefined, "Alice cannot step while a capture is available");
+
+    const partial_jump = await alice_move({ Jump: { from: { x: 1, y: 5 }, path: [{ x: 3, y: 3 }] } });
+    t.equal(partial_jump.Ok, undefined, "Alice cannot stop jumping while another capture is available");
+
+    const full_jump = await alice_move({
+      Jump: { from: { x: 1, y: 5 }, path: [{ x: 3, y: 3 }, { x: 1, y: 1 }] }
+    });
+    t.equal(full_jump.Err, undefined, "Alice can make both captures");
+
+    const state = await bob.callSync("main", "get_state", {
+      game_address: game
+    });
+    t.equal(state.Ok.board[2][4], null, "The first piece jumped is captured");
+    t.equal(state.Ok.board[2][2], null, "The second piece jumped is captured");
+    t.deepEqual(state.Ok.board[1][1], { Man: 1 }, "Alice's piece lands on (1, 1)");
+  }
+);
+
+diorama.registerScenario(
+  "Can claim a timeout when the opponent runs out of time",
+  async (s, t, { alice, bob }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: currentTime(),
+      options: { time_control: { PerMove: { seconds: 3 } } }
+    });
+    const game = create_game_result.Ok;
+
+    const move_result = await bob.callSync("main", "make_move", {
+      new_move: { game, move_type: { DropPiece: { column: 0 } }, timestamp: currentTime() }
+    });
+    t.equal(move_result.Err, undefined, "Bob moves within his time");
+
+    const early_claim = await bob.callSync("main", "make_move", {
+      new_move: { game, move_type: "ClaimTimeout", timestamp: currentTime() }
+    });
+    t.equal(early_claim.Ok, undefined, "Bob cannot claim before Alice's time is up");
+
+    // Close enough to the commit time to be accepted, but after Alice's 3 seconds are up.
+    // Only checking the clock at the commit time as well can reject it.
+    const future_claim = await bob.callSync("main", "make_move", {
+      new_move: { game, move_type: "ClaimTimeout", timestamp: currentTime() + 4 }
+    });
+    t.equal(
+      future_claim.Ok,
+      undefined,
+      "Bob cannot claim early by dating the claim after Alice's time is up"
+    );
+
+    // Wait until Alice's time has really run out
+    await sleep(5000);
+
+    const claim_result = await bob.callSync("main", "make_move", {
+      new_move: { game, move_type: "ClaimTimeout", timestamp: currentTime() }
+    });
+    t.equal(claim_result.Err, undefined, "Bob can claim once Alice's time is up");
+
+    const state = await alice.callSync("main", "get_state", {
+      game_address: game
+    });
+    t.deepEqual(
+      state.Ok.outcome,
+      { Timeout: { winner: 2 } },
+      "Bob wins on time"
+    );
+  }
+);
+
+diorama.registerScenario(
+  "Long games are checkpointed without changing their state",
+  async (s, t, { alice, bob }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: now
+    });
+    const game = create_game_result.Ok;
+
+    // Twelve moves that fill the bottom row and start the next without making a line of four
+    const columns = [0, 1, 2, 3, 4, 5, 6, 0, 1, 2, 3, 4];
+    for (let i = 0; i < columns.length; i++) {
+      const player = i % 2 === 0 ? bob : alice;
+      const move_result = await player.callSync("main", "make_move", {
+        new_move: { game, move_type: { DropPiece: { column: columns[i] } }, timestamp: currentTime() }
+      });
+      t.equal(move_result.Err, undefined, `Move ${i + 1} is made`);
+    }
+
+    const checkpoints = await alice.callSync("main", "get_checkpoints", {
+      game_address: game
+    });
+    t.deepEqual(
+      checkpoints.Ok.map(checkpoint => checkpoint.ply),
+      [10],
+      "A checkpoint is published after the tenth move"
+    );
+
+    const state = await alice.callSync("main", "get_state", {
+      game_address: game
+    });
+    const replayed_state = await alice.callSync("main", "get_state_at", {
+      game_address: game,
+      ply: columns.length
+    });
+    t.deepEqual(
+      state.Ok,
+      replayed_state.Ok,
+      "The state loaded from the checkpoint matches replaying every move"
+    );
+
+    const since_start = await bob.callSync("main", "get_state_since", {
+      game_address: game,
+      since: { Ply: 0 }
+    });
+    const since_checkpoint = await bob.callSync("main", "get_state_since", {
+      game_address: game,
+      since: { Ply: 11 }
+    });
+    t.equal(since_start.Ok.moves.length, columns.length, "Polling from the start returns every move");
+    t.deepEqual(
+      since_checkpoint.Ok.moves,
+      since_start.Ok.moves.slice(11),
+      "Polling across the checkpoint returns the same moves as a full replay"
+    );
+    t.deepEqual(since_checkpoint.Ok.status, since_start.Ok.status, "Polling from either point gives the same status");
+  }
+);
+
+diorama.registerScenario(
+  "Only the players of a game can make moves in it",
+  async (s, t, { alice, bob, carol }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: now
+    });
+    const game = create_game_result.Ok;
+
+    const carol_move = await carol.callSync("main", "make_move", {
+      new_move: { game, move_type: { DropPiece: { column: 0 } }, timestamp: now + 1 }
+    });
+    t.equal(carol_move.Ok, undefined, "Carol cannot move in Alice and Bob's game");
+    t.equal(
+      JSON.parse(carol_move.Err.Internal),
+      "NotAPlayer",
+      "Moves by agents outside the game are rejected with NotAPlayer"
+    );
+
+    const carol_resign = await carol.callSync("main", "make_move", {
+      new_move: { game, move_type: "Resign", timestamp: now + 1 }
+    });
+    t.equal(carol_resign.Ok, undefined, "Carol cannot resign on behalf of a player");
+  }
+);
+
+diorama.registerScenario(
+  "Games must be between real agents at a real time",
+  async (s, t, { alice, bob }) => {
+    const bogus_opponent = await alice.callSync("main", "create_game", {
+      opponent: "not-an-agent",
+      timestamp: now
+    });
+    t.equal(bogus_opponent.Ok, undefined, "The opponent must be an agent address");
+
+    const bogus_time = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: 0
+    });
+    t.equal(bogus_time.Ok, undefined, "The creation time must be a real date");
+  }
+);
+
+diorama.registerScenario(
+  "Boards can be sized per game",
+  async (s, t, { alice, bob }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: now,
+      options: { board_width: 8, board_height: 7 }
+    });
+    t.equal(create_game_result.Ok.length, 46, "Alice can create an 8x7 game");
+    const game = create_game_result.Ok;
+
+    const outside_move = await bob.callSync("main", "make_move", {
+      new_move: { game, move_type: drop(8), timestamp: currentTime() }
+    });
+    t.deepEqual(
+      JSON.parse(outside_move.Err.Internal),
+      { ColumnOutOfBounds: { column: 8 } },
+      "The ninth column is off the board"
+    );
+
+    const last_column_move = await bob.callSync("main", "make_move", {
+      new_move: { game, move_type: drop(7), timestamp: currentTime() }
+    });
+    t.equal(last_column_move.Err, undefined, "Bob can drop a piece in the eighth column");
+
+    const state = await alice.callSync("main", "get_state", {
+      game_address: game
+    });
+    t.equal(state.Ok.grid.length, 8, "The grid has 8 columns");
+    t.equal(state.Ok.grid[7].length, 7, "Each column is 7 rows high");
+    t.equal(state.Ok.grid[7][0], 2, "Bob's piece is at the bottom of the eighth column");
+
+    const wide_game = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: now,
+      options: { board_width: 11, board_height: 7 }
+    });
+    t.equal(wide_game.Ok, undefined, "Boards cannot be wider than 10 columns");
+  }
+);
+
+diorama.registerScenario(
+  "The length of a winning line can be chosen per game",
+  async (s, t, { alice, bob }) => {
+    for (const win_length of [2, 8]) {
+      const rejected_game = await alice.callSync("main", "create_game", {
+        opponent: bob.agentId,
+        timestamp: now,
+        options: { win_length }
+      });
+      t.equal(rejected_game.Ok, undefined, `Connect ${win_length} cannot be played on a 7x6 board`);
+    }
+
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: now,
+      options: { win_length: 3 }
+    });
+    const game = create_game_result.Ok;
+
+    const moves = await playInTurn(game, { alice, bob }, [drop(0), drop(1), drop(0), drop(1), drop(0)]);
+    t.ok(moves.every(result => result.Err === undefined), "Every move is made");
+
+    const state = await alice.callSync("main", "get_state", {
+      game_address: game
+    });
+    t.equal(state.Ok.in_progress, false, "Three in a row ends the game");
+    t.deepEqual(state.Ok.outcome, { Connected: { winner: 2 } }, "Bob wins with a line of three");
+    t.deepEqual(
+      state.Ok.winning_line,
+      { winner: 2, cells: [[0, 0], [0, 1], [0, 2]] },
+      "The winning line is the bottom three cells of the first column"
+    );
+
+    const render = await bob.callSync("main", "render_state", {
+      game_address: game
+    });
+    const rows = render.Ok.split("\n").filter(line => line.startsWith("|"));
+    t.deepEqual(
+      rows.slice(-3),
+      ["|*|_|_|_|_|_|_|", "|*|1|_|_|_|_|_|", "|*|1|_|_|_|_|_|"],
+      "The winning line is marked with * in place of Bob's pieces"
+    );
+  }
+);
+
+diorama.registerScenario(
+  "Players can only pop out their own pieces in PopOut games",
+  async (s, t, { alice, bob }) => {
+    const classic_game = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: now
+    });
+    const classic_moves = await playInTurn(classic_game.Ok, { alice, bob }, [drop(0), drop(1)]);
+    t.ok(classic_moves.every(result => result.Err === undefined), "Pieces can be dropped in a Classic game");
+    const classic_pop = await bob.callSync("main", "make_move", {
+      new_move: { game: classic_game.Ok, move_type: { PopOut: { column: 0 } }, timestamp: currentTime() }
+    });
+    t.equal(classic_pop.Ok, undefined, "Pieces cannot be popped out in a Classic game");
+
+    const popout_game = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: now,
+      options: { variant: "PopOut" }
+    });
+    await playInTurn(popout_game.Ok, { alice, bob }, [drop(0)]);
+    const opponent_pop = await alice.callSync("main", "make_move", {
+      new_move: { game: popout_game.Ok, move_type: { PopOut: { column: 0 } }, timestamp: currentTime() }
+    });
+    t.equal(opponent_pop.Ok, undefined, "Alice cannot pop out Bob's piece");
+  }
+);
+
+diorama.registerScenario(
+  "A pop that completes lines for both players is won by the player who popped",
+  async (s, t, { alice, bob }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: now,
+      options: { board_width: 3, board_height: 4, win_length: 3, variant: "PopOut" }
+    });
+    const game = create_game_result.Ok;
+
+    // Popping Alice's piece out of the first column drops a line into place for each player
+    //  _ _ _                         _ _ _
+    //  1 2 2    ->  Alice pops  ->   _ 2 2
+    //  2 1 1                         1 1 1
+    //  1 2 2                         2 2 2
+    const moves = await playInTurn(game, { alice, bob }, [
+      drop(1), drop(0), drop(0), drop(1), drop(2), drop(2), drop(1), drop(0), drop(2),
+      { PopOut: { column: 0 } }
+    ]);
+    t.ok(moves.every(result => result.Err === undefined), "Every move is made");
+
+    const state = await bob.callSync("main", "get_state", {
+      game_address: game
+    });
+    t.equal(state.Ok.in_progress, false, "The game is over");
+    t.deepEqual(state.Ok.outcome, { Connected: { winner: 1 } }, "Alice wins by popping, although Bob has a line too");
+  }
+);
+
+diorama.registerScenario(
+  "A full board does not end a PopOut game while the next player can pop",
+  async (s, t, { alice, bob }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: now,
+      options: { board_width: 3, board_height: 3, win_length: 3, variant: "PopOut" }
+    });
+    const game = create_game_result.Ok;
+
+    // 2 1 2
+    // 2 1 1
+    // 1 2 2
+    const moves = await playInTurn(game, { alice, bob }, [
+      drop(1), drop(0), drop(2), drop(1), drop(0), drop(2), drop(0), drop(1), drop(2)
+    ]);
+    t.ok(moves.every(result => result.Err === undefined), "The board is filled without a line");
+
+    const state = await bob.callSync("main", "get_state", {
+      game_address: game
+    });
+    t.equal(state.Ok.in_progress, true, "The game carries on with a full board");
+
+    const legal_moves = await alice.callSync("main", "get_legal_moves", {
+      game_address: game,
+      player: null,
+      timestamp: currentTime()
+    });
+    t.deepEqual(
+      legal_moves.Ok.moves.filter(m => m.PopOut || m.DropPiece),
+      [{ PopOut: { column: 0 } }],
+      "Alice can only pop out the piece at the bottom of the first column"
+    );
+  }
+);
+
+diorama.registerScenario(
+  "A player cannot take back a move by making another in its place",
+  async (s, t, { alice, bob }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: now
+    });
+    const game = create_game_result.Ok;
+
+    const first_move_time = currentTime();
+    await bob.callSync("main", "make_move", {
+      new_move: { game, move_type: { DropPiece: { column: 0 } }, timestamp: first_move_time }
+    });
+    await alice.callSync("main", "make_move", {
+      new_move: { game, move_type: { DropPiece: { column: 1 } }, timestamp: currentTime() }
+    });
+
+    // Dated before the move it would replace, so the timestamp cannot help it win a fork either
+    const replacement = await bob.callSync("main", "commit_move", {
+      new_move: {
+        game,
+        author: bob.agentId,
+        move_type: { DropPiece: { column: 3 } },
+        previous_move: game,
+        timestamp: Math.max(now, first_move_time - 1)
+      }
+    });
+    t.equal(replacement.Ok, undefined, "Bob cannot make a second first move");
+    t.ok(
+      JSON.stringify(replacement.Err).includes("already made a move following on from this one"),
+      "The second move is rejected because Bob already moved from the same point"
+    );
+
+    const state = await alice.callSync("main", "get_state", {
+      game_address: game
+    });
+    t.deepEqual(
+      state.Ok.moves_history.map(m => m.move_type.DropPiece.column),
+      [0, 1],
+      "The game carries on from Bob's original move"
+    );
+    const forks = await alice.callSync("main", "get_forks", {
+      game_address: game
+    });
+    t.deepEqual(forks.Ok, [], "The rejected move does not fork the game");
   }
 );
 
@@ -0,0 +1,52 @@
+use hdk::holochain_json_api::{
+    error::JsonError, json::JsonString,
+};
+
+/**
+ *
+ * The MoveType enum defines all the types of moves that are valid in Checkers and the 
+ * data they carry. A piece can step diagonally to an empty square (MovePiece) or capture
+ * one or more of the opponent's pieces by jumping over them (Jump).
+ *
+ */
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Position {
+    pub x: u32,
+    pub y: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
+pub enum MoveType {
+    MovePiece {
+        from: Position,
+        to: Position,
+    },
+    /// Each entry in `path` is a square landed on after jumping an opponent's piece
+    Jump {
+        from: Position,
+        path: Vec<Position>,
+    },
+    Resign,
+    /// Claims the win when the opponent has run out of time to make their move
+    ClaimTimeout,
+}
+
+impl MoveType {
+    pub fn describe() -> Vec<MoveType> {
+        vec![
+            MoveType::MovePiece{from: Position{x: 1, y: 2}, to: Position{x: 2, y: 3}},
+            MoveType::Jump{from: Position{x: 1, y: 2}, path: vec![Position{x: 3, y: 4}, Position{x: 5, y: 6}]},
+            MoveType::Resign,
+            MoveType::ClaimTimeout,
+        ]
+    }
+
+    /// Moves that use up a player's turn. Resigning and timeout claims happen outside of turns.
+    pub fn takes_turn(&self) -> bool {
+        match self {
+            MoveType::MovePiece{..} | MoveType::Jump{..} => true,
+            _ => false,
+        }
+    }
+}
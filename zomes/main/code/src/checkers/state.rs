@@ -0,0 +1,365 @@
+use hdk::holochain_json_api::{
+    error::JsonError, json::JsonString,
+};
+
+use crate::game_move::Move;
+use crate::game::{Game, opponent_of};
+use crate::game_rules::GameRules;
+use super::{MoveType, Position};
+use crate::clock::Clock;
+use hdk::holochain_persistence_api::cas::content::Address;
+
+pub const BOARD_SIZE: u32 = 8;
+
+/**
+ * Board structure:
+ *
+ *    0 1 2 3 4 5 6 7
+ * 7 |_|x|_|x|_|x|_|x|
+ * 6 |x|_|x|_|x|_|x|_|
+ * 5 |_|x|_|x|_|x|_|x|
+ * 4 |_|_|_|_|_|_|_|_|
+ * 3 |_|_|_|_|_|_|_|_|
+ * 2 |o|_|o|_|o|_|o|_|
+ * 1 |_|o|_|o|_|o|_|o|
+ * 0 |o|_|o|_|o|_|o|_|
+ *
+ * Player 2 (o) moves first from the bottom of the board, player 1 (x) starts at the top.
+ * Crowned pieces are shown in upper case.
+ */
+
+/// Indexed as board[x][y], with y = 0 at the bottom
+pub type Board = Vec<Vec<Option<Piece>>>;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub enum Piece {
+    Man(u8),
+    King(u8),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
+pub enum GameOutcome {
+    /// The loser has no pieces or no legal moves left
+    NoMovesLeft { winner: u8 },
+    /// The loser resigned
+    Resigned { winner: u8 },
+    /// The loser ran out of time on their clock
+    Timeout { winner: u8 },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson)]
+pub struct GameState {
+    pub board: Board,
+    pub moves_history: Vec<Move<MoveType>>,
+    pub in_progress: bool,
+    pub outcome: Option<GameOutcome>,
+    pub clock: Clock,
+}
+
+impl Piece {
+    pub fn player(&self) -> u8 {
+        match self {
+            Piece::Man(player) | Piece::King(player) => *player,
+        }
+    }
+
+    /// The directions along the y axis this piece can move in. Men may only move forwards.
+    pub fn directions(&self) -> Vec<i64> {
+        match self {
+            Piece::King(_) => vec![1, -1],
+            Piece::Man(2) => vec![1],
+            Piece::Man(_) => vec![-1],
+        }
+    }
+
+    /// Whether a man arriving at this position is crowned
+    pub fn crowns_at(&self, position: Position) -> bool {
+        match self {
+            Piece::Man(2) => position.y == BOARD_SIZE - 1,
+            Piece::Man(_) => position.y == 0,
+            Piece::King(_) => false,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Piece::Man(1) => "x",
+            Piece::King(1) => "X",
+            Piece::Man(_) => "o",
+            Piece::King(_) => "O",
+        }
+    }
+}
+
+impl GameOutcome {
+    /// The number of the winning player. Checkers games cannot be drawn.
+    pub fn winner(&self) -> Option<u8> {
+        match self {
+            GameOutcome::NoMovesLeft{winner} |
+            GameOutcome::Resigned{winner} |
+            GameOutcome::Timeout{winner} => Some(*winner),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            GameOutcome::NoMovesLeft{winner} => format!("Player {} has no moves left, Player {} wins!", opponent_of(*winner), winner),
+            GameOutcome::Resigned{winner} => format!("Player {} has resigned, Player {} wins!", opponent_of(*winner), winner),
+            GameOutcome::Timeout{winner} => format!("Player {} ran out of time, Player {} wins!", opponent_of(*winner), winner),
+        }
+    }
+}
+
+impl GameState {
+    pub fn initial(game: &Game) -> Self {
+        let size = BOARD_SIZE as usize;
+        let mut board: Board = vec![vec![None; size]; size];
+
+        // Pieces sit on the dark squares of the three rows closest to each player
+        for x in 0..size {
+            for y in 0..size {
+                if (x + y) % 2 == 0 {
+                    if y < 3 {
+                        board[x][y] = Some(Piece::Man(2));
+                    } else if y >= size - 3 {
+                        board[x][y] = Some(Piece::Man(1));
+                    }
+                }
+            }
+        }
+
+        Self {
+            board,
+            moves_history: Vec::new(),
+            in_progress: true,
+            outcome: None,
+            clock: Clock::new(&game.time_control, game.created_at),
+        }
+    }
+
+    /// Renders the game as seen by `viewer`, or as a spectator if there is no viewer or they are not playing
+    pub fn render(&self, game: &Game, viewer: Option<&Address>) -> String {
+        // return a pretty formatting string representation for cli
+        let viewer_number = viewer
+            .and_then(|viewer| game.player_number(viewer));
+        let mut disp = self.render_header(game, viewer_number);
+
+        // render board
+        disp.push_str("  ");
+        for x in 0..(self.board.len()) {
+            disp.push_str(&format!(" {}", x));
+        }
+        disp.push('\n');
+        for y in (0..(self.board[0].len())).rev() {
+            disp.push_str(&format!("{} |", y));
+            for x in 0..(self.board.len()) {
+                let piece = self.board[x][y].map_or("_", |piece| piece.symbol());
+                disp.push_str(&format!("{}|", piece));
+            }
+            disp.push('\n');
+        }
+
+        if let Some(outcome) = &self.outcome {
+            disp.push_str(&format!("Game over: {}\n", outcome.describe()));
+        }
+
+        disp
+    }
+
+    pub fn evolve(&self, game: &Game, next_move: &Move<MoveType>) -> GameState {
+        // given a current state, a game and a move, compute the next state
+        // You can assume all moves are valid, so are always made by one of the players
+        let player = match game.player_number(&next_move.author) {
+            Some(player) => player,
+            None => return self.clone(),
+        };
+
+        // Prepare variables for evolution
+        let mut board = self.board.clone();
+        let mut moves_history = self.moves_history.clone();
+        let mut outcome = self.outcome.clone();
+        let mut clock = self.clock.clone();
+
+        // Record move
+        moves_history.push(next_move.clone());
+
+        // Mutate state
+        match &next_move.move_type {
+            MoveType::MovePiece{from, to} => {
+                board = self.move_piece(board, *from, *to);
+                outcome = self.check_finish(&board, player);
+            },
+            MoveType::Jump{from, path} => {
+                let mut current = *from;
+                for landing in path {
+                    board = self.move_piece(board, current, *landing);
+                    let jumped = midpoint(current, *landing);
+                    board[jumped.x as usize][jumped.y as usize] = None;
+                    current = *landing;
+                }
+                outcome = self.check_finish(&board, player);
+            },
+            MoveType::Resign => {
+                outcome = Some(GameOutcome::Resigned{winner: opponent_of(player)});
+            },
+            MoveType::ClaimTimeout => {
+                outcome = Some(GameOutcome::Timeout{winner: player});
+            }
+        }
+
+        if next_move.move_type.takes_turn() {
+            clock.record_turn(&game.time_control, player, next_move.timestamp);
+        }
+
+        // Return mutated state
+        GameState {
+            board,
+            moves_history,
+            in_progress: outcome.is_none(),
+            outcome,
+            clock,
+        }
+    }
+
+    /// Every step and complete or partial jump path `player` could make, whether or not it is currently valid
+    pub fn candidate_moves(&self, player: u8) -> Vec<MoveType> {
+        let mut candidates = Vec::new();
+        for (from, piece) in player_pieces(&self.board, player) {
+            for to in step_targets(&self.board, from, piece) {
+                candidates.push(MoveType::MovePiece{from, to});
+            }
+            // The jumping piece leaves its starting square, as in validation
+            let mut board = self.board.clone();
+            board[from.x as usize][from.y as usize] = None;
+            for path in jump_paths(&board, from, piece, &[]) {
+                candidates.push(MoveType::Jump{from, path});
+            }
+        }
+        candidates.push(MoveType::Resign);
+        candidates.push(MoveType::ClaimTimeout);
+        candidates
+    }
+
+    fn move_piece(&self, mut board: Board, from: Position, to: Position) -> Board {
+        if let Some(piece) = board[from.x as usize][from.y as usize].take() {
+            // Men reaching the far side of the board are crowned
+            let piece = if piece.crowns_at(to) { Piece::King(piece.player()) } else { piece };
+            board[to.x as usize][to.y as usize] = Some(piece);
+        }
+        board
+    }
+
+    fn check_finish(&self, board: &Board, player: u8) -> Option<GameOutcome> {
+        // A player who cannot move on their turn loses, including when they have no pieces left
+        if has_any_move(board, opponent_of(player)) {
+            None
+        } else {
+            Some(GameOutcome::NoMovesLeft{winner: player})
+        }
+    }
+}
+
+/*=========================================
+=            Movement helpers             =
+=========================================*/
+
+pub fn piece_at(board: &Board, position: Position) -> Option<Piece> {
+    board[position.x as usize][position.y as usize]
+}
+
+/// The square diagonally offset from a position, if it is on the board
+pub fn offset(position: Position, dx: i64, dy: i64) -> Option<Position> {
+    let x = position.x as i64 + dx;
+    let y = position.y as i64 + dy;
+    if x >= 0 && y >= 0 && x < BOARD_SIZE as i64 && y < BOARD_SIZE as i64 {
+        Some(Position{x: x as u32, y: y as u32})
+    } else {
+        None
+    }
+}
+
+/// The square jumped over when moving between two positions two squares apart
+pub fn midpoint(from: Position, to: Position) -> Position {
+    Position {
+        x: (from.x + to.x) / 2,
+        y: (from.y + to.y) / 2,
+    }
+}
+
+/// Empty squares a piece can step to without capturing
+pub fn step_targets(board: &Board, from: Position, piece: Piece) -> Vec<Position> {
+    let mut targets = Vec::new();
+    for dy in piece.directions() {
+        for dx in vec![1, -1] {
+            if let Some(to) = offset(from, dx, dy) {
+                if piece_at(board, to).is_none() {
+                    targets.push(to);
+                }
+            }
+        }
+    }
+    targets
+}
+
+/// Squares a piece can land on by jumping an opponent's piece that has not already been captured this turn
+pub fn jump_targets(board: &Board, from: Position, piece: Piece, captured: &[Position]) -> Vec<Position> {
+    let mut targets = Vec::new();
+    for dy in piece.directions() {
+        for dx in vec![1, -1] {
+            if let (Some(over), Some(to)) = (offset(from, dx, dy), offset(from, 2 * dx, 2 * dy)) {
+                let is_opponent = piece_at(board, over).map_or(false, |jumped| jumped.player() != piece.player());
+                if is_opponent && !captured.contains(&over) && piece_at(board, to).is_none() {
+                    targets.push(to);
+                }
+            }
+        }
+    }
+    targets
+}
+
+/// Every sequence of landing squares a piece can jump along from a position, including those that stop early
+pub fn jump_paths(board: &Board, from: Position, piece: Piece, captured: &[Position]) -> Vec<Vec<Position>> {
+    let mut paths = Vec::new();
+    for landing in jump_targets(board, from, piece, captured) {
+        paths.push(vec![landing]);
+        if piece.crowns_at(landing) {
+            continue
+        }
+        let mut captured = captured.to_vec();
+        captured.push(midpoint(from, landing));
+        for rest in jump_paths(board, landing, piece, &captured) {
+            let mut path = vec![landing];
+            path.extend(rest);
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// The positions of all of a player's pieces
+pub fn player_pieces(board: &Board, player: u8) -> Vec<(Position, Piece)> {
+    let mut pieces = Vec::new();
+    for x in 0..(board.len()) {
+        for y in 0..(board[x].len()) {
+            if let Some(piece) = board[x][y] {
+                if piece.player() == player {
+                    pieces.push((Position{x: x as u32, y: y as u32}, piece));
+                }
+            }
+        }
+    }
+    pieces
+}
+
+pub fn has_any_jump(board: &Board, player: u8) -> bool {
+    player_pieces(board, player).into_iter()
+        .any(|(position, piece)| !jump_targets(board, position, piece, &[]).is_empty())
+}
+
+pub fn has_any_move(board: &Board, player: u8) -> bool {
+    has_any_jump(board, player) ||
+    player_pieces(board, player).into_iter()
+        .any(|(position, piece)| !step_targets(board, position, piece).is_empty())
+}
+
+/*=====  End of Movement helpers  ======*/
@@ -0,0 +1,148 @@
+use crate::error::GameError;
+use crate::connect_four::Variant;
+use crate::game::Game;
+use crate::game_move::Move;
+use crate::game_rules::{
+    is_game_in_progress,
+    is_player_in_game,
+    is_players_turn_in_time,
+    is_valid_timeout_claim,
+};
+use super::{
+    GameState,
+    Position,
+};
+use super::state::{
+    BOARD_SIZE,
+    Piece,
+    piece_at,
+    midpoint,
+    step_targets,
+    jump_targets,
+    has_any_jump,
+};
+
+use super::moves::MoveType;
+
+pub fn validate_game(game: &Game) -> Result<(), String> {
+    // Checkers is always played on an 8x8 board so the Connect Four settings do not apply
+    if game.board_width != BOARD_SIZE || game.board_height != BOARD_SIZE {
+        return Err(format!("Checkers is played on a {}x{} board", BOARD_SIZE, BOARD_SIZE))
+    }
+    if game.win_length != 0 {
+        return Err("Checkers games do not have a win length".into())
+    }
+    match game.variant {
+        Variant::Classic => Ok(()),
+        _ => Err("Checkers games cannot use Connect Four variants".into()),
+    }
+}
+
+/**
+ *
+ * The Checkers rules are validated by the function `is_valid` on `Move`
+ * 
+ * Captures are compulsory: if any of a player's pieces can jump, a plain MovePiece is invalid.
+ * A Jump must also continue for as long as the jumping piece can keep capturing, unless it is
+ * crowned on its last landing square, which always ends the move.
+ *
+ */
+
+impl Move<MoveType> {
+    pub fn is_valid(&self, game: &Game, game_state: &GameState) -> Result<(), GameError> {
+        // Check if a move is valid given the current game and its state
+        is_game_in_progress(game_state)?;
+
+        match &self.move_type {
+            MoveType::MovePiece{from, to} => {
+                let player = is_players_turn_in_time(game, game_state, self)?;
+                let piece = is_players_piece(player, game_state, *from)?;
+                is_no_capture_available(player, game_state)?;
+                is_valid_step(game_state, *from, *to, piece)?;
+            },
+            MoveType::Jump{from, path} => {
+                let player = is_players_turn_in_time(game, game_state, self)?;
+                let piece = is_players_piece(player, game_state, *from)?;
+                is_valid_jump_path(game_state, *from, path, piece)?;
+            },
+            MoveType::Resign => {
+                // A player may resign at any point, whether or not it is their turn
+                is_player_in_game(game, &self.author)?;
+            },
+            MoveType::ClaimTimeout => {
+                is_valid_timeout_claim(game, game_state, self)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn is_on_board(position: Position) -> Result<(), GameError> {
+    if position.x < BOARD_SIZE && position.y < BOARD_SIZE {
+        Ok(())
+    } else {
+        Err(GameError::IllegalMove{reason: format!("Position ({}, {}) is off the board", position.x, position.y)})
+    }
+}
+
+fn is_players_piece(player: u8, game_state: &GameState, position: Position) -> Result<Piece, GameError> {
+    is_on_board(position)?;
+    match piece_at(&game_state.board, position) {
+        Some(piece) if piece.player() == player => Ok(piece),
+        Some(_) => Err(GameError::illegal_move("Can only move your own pieces")),
+        None => Err(GameError::IllegalMove{reason: format!("There is no piece at ({}, {})", position.x, position.y)}),
+    }
+}
+
+fn is_no_capture_available(player: u8, game_state: &GameState) -> Result<(), GameError> {
+    if has_any_jump(&game_state.board, player) {
+        Err(GameError::illegal_move("A capture is available so you must jump"))
+    } else {
+        Ok(())
+    }
+}
+
+fn is_valid_step(game_state: &GameState, from: Position, to: Position, piece: Piece) -> Result<(), GameError> {
+    is_on_board(to)?;
+    if step_targets(&game_state.board, from, piece).contains(&to) {
+        Ok(())
+    } else {
+        Err(GameError::IllegalMove{reason: format!("Cannot move from ({}, {}) to ({}, {})", from.x, from.y, to.x, to.y)})
+    }
+}
+
+fn is_valid_jump_path(game_state: &GameState, from: Position, path: &[Position], piece: Piece) -> Result<(), GameError> {
+    if path.is_empty() {
+        return Err(GameError::illegal_move("A jump must land on at least one square"))
+    }
+
+    // The jumping piece leaves its starting square, captured pieces stay on the board until the move is complete
+    let mut board = game_state.board.clone();
+    board[from.x as usize][from.y as usize] = None;
+
+    let mut captured = Vec::new();
+    let mut current = from;
+    for (i, landing) in path.iter().enumerate() {
+        is_on_board(*landing)?;
+        if !jump_targets(&board, current, piece, &captured).contains(landing) {
+            return Err(GameError::IllegalMove{reason: format!("Cannot jump from ({}, {}) to ({}, {})", current.x, current.y, landing.x, landing.y)})
+        }
+        captured.push(midpoint(current, *landing));
+        current = *landing;
+
+        if piece.crowns_at(current) {
+            if i + 1 < path.len() {
+                return Err(GameError::illegal_move("A piece that is crowned ends its move"))
+            }
+            return Ok(())
+        }
+    }
+
+    // Multi-jumps must be completed
+    if jump_targets(&board, current, piece, &captured).is_empty() {
+        Ok(())
+    } else {
+        Err(GameError::illegal_move("Must keep jumping while further captures are available"))
+    }
+}
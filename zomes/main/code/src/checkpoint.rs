@@ -0,0 +1,231 @@
+use std::collections::{BTreeMap, HashMap};
+use serde_json::Value as JsonValue;
+use hdk::{
+    entry_definition::ValidatingEntryType,
+    error::ZomeApiResult,
+    holochain_persistence_api::{
+        cas::content::{Address},
+    },
+    holochain_json_api::{
+        error::JsonError, json::JsonString,
+    },
+    holochain_core_types::{
+        dna::entry_types::Sharing,
+        validation::EntryValidationData,
+        entry::Entry,
+        link::LinkMatch,
+    }
+};
+
+use crate::error::GameError;
+use crate::game::{self, Game, Snapshot};
+use crate::game_rules::GameRules;
+
+/**
+ *
+ * Replaying a long game means fetching every one of its moves from the DHT. To avoid this the player
+ * who makes every CHECKPOINT_INTERVAL'th move also publishes a snapshot of the state after it, linked from
+ * the game. Validating a move or loading a game's state then only needs the moves since the nearest checkpoint.
+ *
+ * Checkpoints are validated by recomputing the state they claim from the game's moves, so they can be trusted
+ * as much as the moves themselves.
+ *
+ */
+
+pub const CHECKPOINT_INTERVAL: usize = 10;
+
+const CHECKPOINT_LINK_TYPE: &str = "state_checkpoint";
+
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
+pub struct StateCheckpoint {
+    pub game: Address,
+    /// The move the state was computed after
+    pub last_move: Address,
+    pub ply: usize,
+    pub last_timestamp: u32,
+    /// The game's State, in the format of the rules the game is played with
+    pub state: JsonValue,
+}
+
+impl StateCheckpoint {
+    fn from_snapshot<R: GameRules>(game_address: &Address, snapshot: &Snapshot<R>) -> Result<Self, GameError> {
+        Ok(StateCheckpoint {
+            game: game_address.clone(),
+            last_move: snapshot.last_move.clone(),
+            ply: snapshot.ply,
+            last_timestamp: snapshot.last_timestamp,
+            state: serde_json::to_value(&snapshot.state)
+                .map_err(|e| GameError::CorruptEntry{address: snapshot.last_move.clone(), reason: e.to_string()})?,
+        })
+    }
+
+    fn into_snapshot<R: GameRules>(self, address: &Address) -> Result<Snapshot<R>, GameError> {
+        Ok(Snapshot {
+            state: serde_json::from_value(self.state)
+                .map_err(|e| GameError::CorruptEntry{address: address.clone(), reason: e.to_string()})?,
+            last_move: self.last_move,
+            ply: self.ply,
+            last_timestamp: self.last_timestamp,
+        })
+    }
+}
+
+/// Links to checkpoints are tagged with "<ply>:<last move address>" so they can be found without loading them
+fn link_tag(checkpoint: &StateCheckpoint) -> String {
+    format!("{}:{}", checkpoint.ply, checkpoint.last_move)
+}
+
+fn parse_link_tag(tag: &str) -> Option<(usize, Address)> {
+    let mut parts = tag.splitn(2, ':');
+    let ply = parts.next()?.parse().ok()?;
+    let last_move = parts.next()?;
+    Some((ply, last_move.into()))
+}
+
+/// Publishes a checkpoint if the snapshot is due one
+pub fn commit_if_due<R: GameRules>(game_address: &Address, snapshot: &Snapshot<R>) -> ZomeApiResult<()> {
+    if snapshot.ply == 0 || snapshot.ply % CHECKPOINT_INTERVAL != 0 {
+        return Ok(())
+    }
+    let checkpoint = StateCheckpoint::from_snapshot(game_address, snapshot)?;
+    let tag = link_tag(&checkpoint);
+    let checkpoint_address = hdk::commit_entry(&Entry::App(
+        "state_checkpoint".into(),
+        checkpoint.into(),
+    ))?;
+    hdk::link_entries(game_address, &checkpoint_address, CHECKPOINT_LINK_TYPE, &tag)?;
+    Ok(())
+}
+
+/// The ply, last move and address of each of a game's checkpoints, read from the link tags
+fn get_checkpoint_links(game_address: &Address) -> ZomeApiResult<Vec<(usize, Address, Address)>> {
+    Ok(hdk::get_links(game_address, LinkMatch::Exactly(CHECKPOINT_LINK_TYPE), LinkMatch::Any)?
+        .links()
+        .into_iter()
+        .filter_map(|link| parse_link_tag(&link.tag).map(|(ply, last_move)| (ply, last_move, link.address)))
+        .collect())
+}
+
+/// The address of each of a game's checkpoints made before `below_ply` moves, keyed by the move they were computed after
+pub fn get_checkpoint_addresses(game_address: &Address, below_ply: usize) -> ZomeApiResult<HashMap<Address, Address>> {
+    Ok(get_checkpoint_links(game_address)?
+        .into_iter()
+        .filter(|(ply, _, _)| *ply < below_ply)
+        .map(|(_, last_move, address)| (last_move, address))
+        .collect())
+}
+
+/// How many moves into the game each of a game's checkpoints was made, keyed by the move they were computed after
+pub fn get_checkpoint_plies(game_address: &Address) -> ZomeApiResult<HashMap<Address, usize>> {
+    Ok(get_checkpoint_links(game_address)?
+        .into_iter()
+        .map(|(ply, last_move, _)| (last_move, ply))
+        .collect())
+}
+
+/// Every checkpoint of a game, oldest first
+pub fn get_checkpoints(game_address: &Address) -> ZomeApiResult<Vec<StateCheckpoint>> {
+    let mut checkpoints = get_checkpoint_addresses(game_address, usize::max_value())?
+        .values()
+        .map(|checkpoint_address| game::get_entry_as(checkpoint_address, "state_checkpoint"))
+        .collect::<Result<Vec<StateCheckpoint>, GameError>>()?;
+    checkpoints.sort_by_key(|checkpoint| checkpoint.ply);
+    Ok(checkpoints)
+}
+
+pub fn load_checkpoint<R: GameRules>(checkpoint_address: &Address) -> ZomeApiResult<Snapshot<R>> {
+    let checkpoint: StateCheckpoint = game::get_entry_as(checkpoint_address, "state_checkpoint")?;
+    Ok(checkpoint.into_snapshot(checkpoint_address)?)
+}
+
+/**
+ * The most recent checkpoint made no later than `ply` moves into a game.
+ *
+ * Players cannot make two moves from the same move, so a fork can only come from one player racing the other
+ * with a move that does not take a turn. Every other move must be the opponent's, so one player alone cannot
+ * carry the losing side of a fork on to a later checkpoint, and the latest checkpoint can be used directly.
+ * If two checkpoints were made at the same ply the fork is left to be resolved from the checkpoint before.
+ */
+pub fn get_latest_checkpoint<R: GameRules>(game_address: &Address, ply: usize) -> ZomeApiResult<Option<Snapshot<R>>> {
+    let mut by_ply: BTreeMap<usize, Vec<Address>> = BTreeMap::new();
+    for (checkpoint_ply, _, checkpoint_address) in get_checkpoint_links(game_address)? {
+        if checkpoint_ply <= ply {
+            let at_ply = by_ply.entry(checkpoint_ply).or_insert_with(Vec::new);
+            if !at_ply.contains(&checkpoint_address) {
+                at_ply.push(checkpoint_address);
+            }
+        }
+    }
+
+    let latest = by_ply.values().rev().find(|at_ply| at_ply.len() == 1);
+    match latest {
+        Some(at_ply) => load_checkpoint(&at_ply[0]).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn validate_checkpoint<R: GameRules>(game: &Game, checkpoint: &StateCheckpoint) -> Result<(), String> {
+    // Only earlier checkpoints can be used, otherwise this one, or another claiming the same move, could vouch for itself
+    let snapshot = game::replay_to::<R>(&checkpoint.game, game, &checkpoint.last_move, checkpoint.ply)
+        .map_err(|_| "Could not replay the game up to the checkpoint")?;
+    if StateCheckpoint::from_snapshot(&checkpoint.game, &snapshot)? == *checkpoint {
+        Ok(())
+    } else {
+        Err("Checkpoint does not match the moves of the game".into())
+    }
+}
+
+pub fn definition() -> ValidatingEntryType {
+    entry!(
+        name: "state_checkpoint",
+        description: "A snapshot of the state of a game after one of its moves",
+        sharing: Sharing::Public,
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: | validation_data: hdk::EntryValidationData<StateCheckpoint>| {
+            match validation_data {
+                EntryValidationData::Create{entry, validation_data: _} => {
+                    let checkpoint = entry as StateCheckpoint;
+                    if checkpoint.ply == 0 || checkpoint.ply % CHECKPOINT_INTERVAL != 0 {
+                        return Err(format!("Checkpoints can only be made every {} moves", CHECKPOINT_INTERVAL))
+                    }
+                    let game = game::get_game(&checkpoint.game)
+                        .map_err(|_| "Could not load game during validation")?;
+                    with_game_rules!(game.game_type, R => validate_checkpoint::<R>(&game, &checkpoint))
+                },
+                _ => {
+                    Err("Cannot modify or delete a checkpoint".into())
+                }
+            }
+        },
+
+        links: [
+            from!(
+                "game",
+                link_type: "state_checkpoint",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: | validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        hdk::LinkValidationData::LinkAdd{link, ..} => {
+                            let checkpoint: StateCheckpoint = game::get_entry_as(link.link().target(), "state_checkpoint")?;
+                            if checkpoint.game != *link.link().base() {
+                                return Err("Checkpoints can only be linked from their own game".into())
+                            }
+                            if link.link().tag() != &link_tag(&checkpoint) {
+                                return Err("Checkpoint link tag does not match the checkpoint".into())
+                            }
+                            Ok(())
+                        },
+                        hdk::LinkValidationData::LinkRemove{..} => {
+                            Err("Cannot remove links to checkpoints".into())
+                        }
+                    }
+                }
+            )
+        ]
+    )
+}
@@ -0,0 +1,171 @@
+use chrono::DateTime;
+use hdk::holochain_json_api::{
+    error::JsonError, json::JsonString,
+};
+use hdk::holochain_core_types::chain_header::ChainHeader;
+
+use crate::error::GameError;
+
+/// No game can have been created before this (2019-01-01), so earlier timestamps are not real dates
+pub const EARLIEST_TIMESTAMP: u32 = 1_546_300_800;
+
+/// How far a move's timestamp may be before the time in the header of the chain it was committed to, in seconds
+pub const MAX_CLOCK_SKEW: u32 = 5 * 60;
+
+/// Timed games charge each turn up to its timestamp, so it must be much closer to the commit time
+/// or players could backdate their turns to save time on their clock
+pub const MAX_TIMED_CLOCK_SKEW: u32 = 10;
+
+/// How far a timestamp may be after the commit time, only allowing for small differences between clocks
+pub const MAX_FUTURE_SKEW: u32 = 5;
+
+/**
+ *
+ * Time controls are shared by every game. A game's State keeps a Clock which is charged
+ * with the time between turn moves, using the timestamps recorded on each move. Once the
+ * player to move has used up their time their opponent can claim the game with a timeout move.
+ *
+ * Moves that do not take a turn (resigning, draw negotiation, timeout claims) leave the clock alone.
+ *
+ */
+
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
+pub enum TimeControl {
+    /// Players can take as long as they like
+    Unlimited,
+    /// Each turn must be made within `seconds` of the previous one
+    PerMove { seconds: u32 },
+    /// Each player starts with `total` seconds and gains `increment` seconds after each of their turns
+    Fischer { total: u32, increment: u32 },
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        TimeControl::Unlimited
+    }
+}
+
+impl TimeControl {
+    pub fn describe(&self) -> String {
+        match self {
+            TimeControl::Unlimited => "No time limit".into(),
+            TimeControl::PerMove{seconds} => format!("{}s per move", seconds),
+            TimeControl::Fischer{total, increment} => format!("{}s + {}s per move", total, increment),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
+pub struct Clock {
+    /// Seconds left on the clocks of player 1 and player 2, not counting the turn in progress.
+    /// None if the game has no time limit.
+    pub remaining: Option<[u32; 2]>,
+    /// When the current turn started. This is the timestamp of the last turn move, or when the game was created.
+    pub turn_started_at: u32,
+}
+
+impl Clock {
+    pub fn new(time_control: &TimeControl, created_at: u32) -> Self {
+        let remaining = match time_control {
+            TimeControl::Unlimited => None,
+            TimeControl::PerMove{seconds} => Some([*seconds, *seconds]),
+            TimeControl::Fischer{total, ..} => Some([*total, *total]),
+        };
+        Clock {
+            remaining,
+            turn_started_at: created_at,
+        }
+    }
+
+    /// Charges `player` for a turn they made at `timestamp` and starts their opponent's turn
+    pub fn record_turn(&mut self, time_control: &TimeControl, player: u8, timestamp: u32) {
+        let elapsed = timestamp.saturating_sub(self.turn_started_at);
+        if let Some(remaining) = self.remaining.as_mut() {
+            let index = player as usize - 1;
+            remaining[index] = match time_control {
+                TimeControl::Unlimited => remaining[index],
+                TimeControl::PerMove{seconds} => *seconds,
+                TimeControl::Fischer{increment, ..} => remaining[index].saturating_sub(elapsed) + increment,
+            };
+        }
+        self.turn_started_at = timestamp;
+    }
+
+    /// Whether `player`, who is to move, has run out of time at `timestamp`
+    pub fn has_expired(&self, player: u8, timestamp: u32) -> bool {
+        match self.remaining {
+            Some(remaining) => timestamp.saturating_sub(self.turn_started_at) > remaining[player as usize - 1],
+            None => false,
+        }
+    }
+
+    // Validation fragment
+    pub fn is_within_time(&self, player: u8, timestamp: u32) -> Result<(), GameError> {
+        if self.has_expired(player, timestamp) {
+            Err(GameError::OutOfTime)
+        } else {
+            Ok(())
+        }
+    }
+
+    // Validation fragment
+    pub fn is_out_of_time(&self, player: u8, timestamp: u32) -> Result<(), GameError> {
+        if self.has_expired(player, timestamp) {
+            Ok(())
+        } else {
+            Err(GameError::illegal_move("Your opponent still has time left on their clock"))
+        }
+    }
+
+    pub fn describe(&self) -> Option<String> {
+        self.remaining.map(|remaining| {
+            format!("Clock at the start of the turn: Player 1 {}s, Player 2 {}s", remaining[0], remaining[1])
+        })
+    }
+}
+
+/*==========================================
+=            Timestamp validation          =
+==========================================*/
+
+/// The time an entry was committed according to its author's chain header, in seconds since the epoch
+pub fn header_timestamp(header: &ChainHeader) -> Result<u32, String> {
+    DateTime::parse_from_rfc3339(&header.timestamp().to_string())
+        .map(|time| time.timestamp() as u32)
+        .map_err(|e| format!("Could not read the header timestamp: {}", e))
+}
+
+/// Moves must not be dated before the move or game they follow
+pub fn is_not_before(timestamp: u32, previous: u32) -> Result<(), String> {
+    if timestamp < previous {
+        Err(format!("Timestamp {} is earlier than the previous move or the game's creation at {}", timestamp, previous))
+    } else {
+        Ok(())
+    }
+}
+
+/// Timestamps are chosen by the author, so they are only trusted when shortly before the time the entry was committed
+pub fn is_close_to_header_time(timestamp: u32, header_time: u32, time_control: &TimeControl) -> Result<(), String> {
+    is_not_after_header_time(timestamp, header_time)?;
+    let max_delay = match time_control {
+        TimeControl::Unlimited => MAX_CLOCK_SKEW,
+        _ => MAX_TIMED_CLOCK_SKEW,
+    };
+    if header_time.saturating_sub(timestamp) > max_delay {
+        Err(format!("Timestamp {} is more than {}s before the commit time {}", timestamp, max_delay, header_time))
+    } else {
+        Ok(())
+    }
+}
+
+/// Nothing can be dated after it was committed. Entries can be committed again long after they were created,
+/// so on its own this does not limit how far in the past a timestamp can be.
+pub fn is_not_after_header_time(timestamp: u32, header_time: u32) -> Result<(), String> {
+    if timestamp > header_time.saturating_add(MAX_FUTURE_SKEW) {
+        Err(format!("Timestamp {} is in the future of the commit time {}", timestamp, header_time))
+    } else {
+        Ok(())
+    }
+}
+
+/*=====  End of Timestamp validation  ======*/
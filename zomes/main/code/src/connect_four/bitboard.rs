@@ -0,0 +1,116 @@
+use super::state::Grid;
+
+/**
+ * Bitboard structure (for the classic 7x6 board):
+ *
+ *    0  1  2  3  4  5  6
+ * 6  6 13 20 27 34 41 48   <- sentinel row, always empty
+ * 5  5 12 19 26 33 40 47
+ * 4  4 11 18 25 32 39 46
+ * 3  3 10 17 24 31 38 45
+ * 2  2  9 16 23 30 37 44
+ * 1  1  8 15 22 29 36 43
+ * 0  0  7 14 21 28 35 42
+ *
+ * Each player's pieces are stored as one bit per cell, column by column. The empty sentinel row on
+ * top of every column stops lines from wrapping around from one column into the next, so a line of
+ * N in any direction can be found with N shifts regardless of the size of the board.
+ *
+ * This needs width * (height + 1) bits, which the board size limits in validation keep within a u128.
+ */
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bitboard {
+    width: usize,
+    height: usize,
+    players: [u128; 2],
+}
+
+impl Bitboard {
+    pub fn from_grid(grid: &Grid) -> Self {
+        let mut bitboard = Bitboard {
+            width: grid.len(),
+            height: grid[0].len(),
+            players: [0, 0],
+        };
+        for col in 0..(grid.len()) {
+            for row in 0..(grid[col].len()) {
+                if grid[col][row] != 0 {
+                    bitboard.players[grid[col][row] as usize - 1] |= bitboard.cell(col, row);
+                }
+            }
+        }
+        bitboard
+    }
+
+    fn cell(&self, col: usize, row: usize) -> u128 {
+        1u128 << (col * (self.height + 1) + row)
+    }
+
+    fn column_mask(&self, col: usize) -> u128 {
+        ((1u128 << self.height) - 1) << (col * (self.height + 1))
+    }
+
+    fn occupied(&self) -> u128 {
+        self.players[0] | self.players[1]
+    }
+
+    /// Adds a piece on top of a column
+    pub fn drop_piece(&mut self, player: u8, col: usize) {
+        let row = (self.occupied() & self.column_mask(col)).count_ones() as usize;
+        self.players[player as usize - 1] |= self.cell(col, row);
+    }
+
+    /// Removes the bottom piece of a column and shifts the rest of the column down
+    pub fn pop_piece(&mut self, col: usize) {
+        let mask = self.column_mask(col);
+        for board in self.players.iter_mut() {
+            let column = *board & mask;
+            // the bottom bit of the column shifts out into the previous column's sentinel, so mask it away
+            *board = (*board & !mask) | ((column >> 1) & mask);
+        }
+    }
+
+    /// Whether a player has at least `win_length` pieces in a line anywhere on the board
+    pub fn has_line(&self, player: u8, win_length: usize) -> bool {
+        let board = self.players[player as usize - 1];
+        self.directions().iter().any(|&shift| Self::line_starts(board, shift, win_length) != 0)
+    }
+
+    /// The (column, row) of every cell that is part of a line of at least `win_length` of a player's pieces
+    pub fn line_cells(&self, player: u8, win_length: usize) -> Vec<(u32, u32)> {
+        let board = self.players[player as usize - 1];
+        let cells = self.directions().iter().fold(0u128, |cells, &shift| {
+            let starts = Self::line_starts(board, shift, win_length);
+            (0..win_length).fold(cells, |cells, step| cells | (starts << (shift * step)))
+        });
+
+        (0..self.width)
+            .flat_map(|col| (0..self.height).map(move |row| (col, row)))
+            .filter(|&(col, row)| cells & self.cell(col, row) != 0)
+            .map(|(col, row)| (col as u32, row as u32))
+            .collect()
+    }
+
+    /// Whether every cell on the board is occupied
+    pub fn is_full(&self) -> bool {
+        let all_cells = (0..self.width).fold(0u128, |mask, col| mask | self.column_mask(col));
+        self.occupied() == all_cells
+    }
+
+    /// Whether a player has a piece on the bottom row of any column
+    pub fn has_bottom_piece(&self, player: u8) -> bool {
+        let bottom_row = (0..self.width).fold(0u128, |mask, col| mask | self.cell(col, 0));
+        self.players[player as usize - 1] & bottom_row != 0
+    }
+
+    /// Bit offsets between neighbouring cells: vertical, horizontal, and the two diagonals
+    fn directions(&self) -> [usize; 4] {
+        [1, self.height + 1, self.height, self.height + 2]
+    }
+
+    /// The cells from which a run of `length` pieces continues in the direction of `shift`
+    fn line_starts(board: u128, shift: usize, length: usize) -> u128 {
+        (1..length).fold(board, |starts, step| starts & (board >> (shift * step)))
+    }
+}
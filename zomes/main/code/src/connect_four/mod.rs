@@ -1,17 +1,96 @@
 /**
- * All of this code is specific to the game Checkers
- * By changing the moves, state, reducer and validation rules you can implement you own game.
+ * All of this code is specific to the game Connect Four
+ * The moves, state, reducer and validation rules are plugged into the zome through the GameRules trait.
  */
 
 pub mod state;
 pub mod validation;
 pub mod moves;
+pub mod bitboard;
 
 pub use self::{
     state::{
         GameState,
+        GameOutcome,
+        Variant,
+        WinningLine,
     },
     moves::{
         MoveType,
     },
 };
+
+use hdk::holochain_persistence_api::cas::content::Address;
+
+use crate::clock::Clock;
+use crate::error::GameError;
+use crate::game::Game;
+use crate::game_move::Move;
+use crate::game_rules::GameRules;
+
+/// Identifies Connect Four in the `game_type` of a Game entry
+pub const GAME_TYPE: &str = "connect_four";
+
+impl GameRules for GameState {
+    type MoveType = MoveType;
+
+    fn validate_game(game: &Game) -> Result<(), String> {
+        validation::validate_game(game)
+    }
+
+    fn initial(game: &Game) -> Self {
+        GameState::initial(game)
+    }
+
+    fn evolve(&self, game: &Game, next_move: &Move<MoveType>) -> Self {
+        GameState::evolve(self, game, next_move)
+    }
+
+    fn apply(&mut self, game: &Game, next_move: &Move<MoveType>) {
+        GameState::apply(self, game, next_move)
+    }
+
+    fn is_valid(&self, game: &Game, next_move: &Move<MoveType>) -> Result<(), GameError> {
+        next_move.is_valid(game, self)
+    }
+
+    fn moves_history(&self) -> &[Move<MoveType>] {
+        &self.moves_history
+    }
+
+    fn in_progress(&self) -> bool {
+        self.in_progress
+    }
+
+    fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    fn takes_turn(move_type: &MoveType) -> bool {
+        move_type.takes_turn()
+    }
+
+    fn candidate_moves(&self, game: &Game, _player: u8) -> Vec<MoveType> {
+        MoveType::candidates(game)
+    }
+
+    fn outcome_winner(&self) -> Option<u8> {
+        self.outcome.as_ref().and_then(GameOutcome::winner)
+    }
+
+    fn outcome_description(&self) -> Option<String> {
+        self.outcome.as_ref().map(GameOutcome::describe)
+    }
+
+    fn player_symbol(player: u8) -> &'static str {
+        state::piece_symbol(player)
+    }
+
+    fn render(&self, game: &Game, viewer: Option<&Address>) -> String {
+        GameState::render(self, game, viewer)
+    }
+
+    fn describe() -> Vec<MoveType> {
+        MoveType::describe()
+    }
+}
@@ -2,10 +2,12 @@ use hdk::holochain_json_api::{
     error::JsonError, json::JsonString,
 };
 
+use crate::game::Game;
+
 /**
  *
- * The MoveType enum defines all the types of moves that are valid in your game and the 
- * data they carry. In Checkers you can move a piece (MovePiece) from a location to another location.
+ * The MoveType enum defines all the types of moves that are valid in Connect Four and the 
+ * data they carry. The main move is dropping a piece (DropPiece) into a column.
  *
  */
 
@@ -14,10 +16,52 @@ pub enum MoveType {
     DropPiece {
         column: u32,
     },
+    PopOut {
+        column: u32,
+    },
+    Resign,
+    OfferDraw,
+    AcceptDraw,
+    DeclineDraw,
+    /// Claims the win when the opponent has run out of time to make their move
+    ClaimTimeout,
 }
 
 impl MoveType {
     pub fn describe() -> Vec<MoveType> {
-        vec![MoveType::DropPiece{column: 0}]
+        vec![
+            MoveType::DropPiece{column: 0},
+            MoveType::PopOut{column: 0},
+            MoveType::Resign,
+            MoveType::OfferDraw,
+            MoveType::AcceptDraw,
+            MoveType::DeclineDraw,
+            MoveType::ClaimTimeout,
+        ]
+    }
+
+    /// Every move that could be made on a game's board, whether or not it is currently valid
+    pub fn candidates(game: &Game) -> Vec<MoveType> {
+        let mut candidates = Vec::new();
+        for column in 0..game.board_width {
+            candidates.push(MoveType::DropPiece{column});
+            candidates.push(MoveType::PopOut{column});
+        }
+        candidates.extend(vec![
+            MoveType::Resign,
+            MoveType::OfferDraw,
+            MoveType::AcceptDraw,
+            MoveType::DeclineDraw,
+            MoveType::ClaimTimeout,
+        ]);
+        candidates
+    }
+
+    /// Moves that use up a player's turn. Resigning, draw negotiation and timeout claims happen outside of turns.
+    pub fn takes_turn(&self) -> bool {
+        match self {
+            MoveType::DropPiece{..} | MoveType::PopOut{..} => true,
+            _ => false,
+        }
     }
 }
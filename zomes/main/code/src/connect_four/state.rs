@@ -3,17 +3,19 @@ use hdk::holochain_json_api::{
 };
 
 use crate::game_move::Move;
-use crate::game::Game;
+use crate::error::GameError;
+use crate::game::{Game, opponent_of};
+use crate::game_rules::GameRules;
 use super::MoveType;
-use hdk::AGENT_ADDRESS;
+use super::bitboard::Bitboard;
+use crate::clock::Clock;
+use hdk::holochain_persistence_api::cas::content::Address;
 
-const ROWS: usize = 6;
-const COLUMNS: usize = 7;
-
-pub type Grid = [[u8; ROWS]; COLUMNS];
+/// Indexed as grid[column][row], with row 0 at the bottom
+pub type Grid = Vec<Vec<u8>>;
 
 /**
- * Grid structure:
+ * Grid structure (for the classic 7x6 board):
  * 
  *    0 1 2 3 4 5 6
  *    _ _ _ _ _ _ _
@@ -31,11 +33,49 @@ pub type Grid = [[u8; ROWS]; COLUMNS];
  * As a game author you get to decide what the State object of your game looks like.
  * Most of the time you want it to include all of the previous moves as well.
  * 
- * To customize the game state implement your own GameState struct. This must have a function called `initial()`
- * which returns the initial state.
+ * The GameRules implementation in mod.rs exposes this state, its `initial()` and `evolve()` functions
+ * and the move validation to the rest of the zome.
  *
  */
 
+/// The rule set a game is played with, chosen when the game is created
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
+pub enum Variant {
+    /// Pieces can only be dropped in
+    Classic,
+    /// Players may also pop their own piece out of the bottom of a column instead of dropping
+    PopOut,
+}
+
+impl Default for Variant {
+    fn default() -> Self {
+        Variant::Classic
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
+pub enum GameOutcome {
+    /// The winner connected the required number of pieces in a line
+    Connected { winner: u8 },
+    /// The loser resigned
+    Resigned { winner: u8 },
+    /// The board filled up without either player connecting a line
+    BoardFull,
+    /// A draw was offered and accepted
+    DrawAgreed,
+    /// The loser ran out of time on their clock
+    Timeout { winner: u8 },
+    /// The game was called off without a result
+    Abandoned,
+}
+
+/// The pieces that won the game, as (column, row) pairs
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
+pub struct WinningLine {
+    pub winner: u8,
+    pub cells: Vec<(u32, u32)>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, DefaultJson)]
 pub struct PlayerState {
     pub resigned: bool,
@@ -44,10 +84,39 @@ pub struct PlayerState {
 #[derive(Clone, Debug, Serialize, Deserialize, DefaultJson)]
 pub struct GameState {
     pub grid: Grid, 
-    pub moves_history: Vec<Move>,
+    pub moves_history: Vec<Move<MoveType>>,
     pub player_1: PlayerState,
     pub player_2: PlayerState,
+    pub draw_offered_by: Option<Address>,
     pub in_progress: bool,
+    pub outcome: Option<GameOutcome>,
+    pub clock: Clock,
+    pub winning_line: Option<WinningLine>,
+    #[serde(skip)]
+    bitboard: Option<Bitboard>,
+}
+
+impl GameOutcome {
+    /// The number of the winning player, if the game had one
+    pub fn winner(&self) -> Option<u8> {
+        match self {
+            GameOutcome::Connected{winner} |
+            GameOutcome::Resigned{winner} |
+            GameOutcome::Timeout{winner} => Some(*winner),
+            _ => None,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            GameOutcome::Connected{winner} => format!("Player {} connected a line and wins!", winner),
+            GameOutcome::Resigned{winner} => format!("Player {} has resigned, Player {} wins!", opponent_of(*winner), winner),
+            GameOutcome::BoardFull => "The board is full, the game is a draw!".into(),
+            GameOutcome::DrawAgreed => "The players agreed to a draw!".into(),
+            GameOutcome::Timeout{winner} => format!("Player {} ran out of time, Player {} wins!", opponent_of(*winner), winner),
+            GameOutcome::Abandoned => "The game was abandoned".into(),
+        }
+    }
 }
 
 impl PlayerState {
@@ -59,234 +128,225 @@ impl PlayerState {
 }
 
 impl GameState {
-    pub fn initial() -> Self {
+    pub fn initial(game: &Game) -> Self {
         Self {
-            grid: [[0; ROWS]; COLUMNS], // initialise grid with zeroes
+            grid: vec![vec![0; game.board_height as usize]; game.board_width as usize], // initialise grid with zeroes
             moves_history: Vec::new(), // flexible size vector
             player_1: PlayerState::initial(),
             player_2: PlayerState::initial(),
+            draw_offered_by: None,
             in_progress: true,
+            outcome: None,
+            clock: Clock::new(&game.time_control, game.created_at),
+            winning_line: None,
+            bitboard: None,
         }
     }
 
-    pub fn render(&self) -> String {
+    /// Renders the game as seen by `viewer`, or as a spectator if there is no viewer or they are not playing
+    pub fn render(&self, game: &Game, viewer: Option<&Address>) -> String {
         // return a pretty formatting string representation for cli
         let grid = self.grid.clone();
+        let viewer_number = viewer
+            .and_then(|viewer| game.player_number(viewer));
 
-        let mut disp = "\n".to_string();
+        let mut disp = self.render_header(game, viewer_number);
 
-        if let Some(last_move) = self.moves_history.last() {
-            if last_move.author.to_string() == AGENT_ADDRESS.to_string() {
-                disp.push_str("It is your opponents turn \n");
-            } else {
-                disp.push_str("It is your turn \n");
-            }
-        } else {
-            disp.push_str("Non-creator must make the first move \n");        
+        // render grid
+        for col in 0..(grid.len()) {
+            disp.push_str(&format!(" {}", col));
         }
         disp.push('\n');
-        
-        // render grid
-        disp.push_str(" 0 1 2 3 4 5 6\n");
-        for row in (0..(ROWS)).rev() {
+        for row in (0..(grid[0].len())).rev() {
             disp.push_str("|");
-            for col in 0..(COLUMNS) {
+            for col in 0..(grid.len()) {
                 let player_piece = match grid[col][row] {
-                    1 => "1",
-                    2 => "2",
-                    _ => "_",
+                    _ if self.is_in_winning_line(col, row) => "*",
+                    0 => "_",
+                    player => piece_symbol(player),
                 };
                 disp.push_str(&format!("{}|", player_piece));
             }
             disp.push('\n');
         }
 
-        if self.player_1.resigned {
-            disp.push_str(&format!("Game over: Player 1 has resigned!\n"));
-        } else if self.player_2.resigned {
-            disp.push_str(&format!("Game over: Player 2 has resigned!\n"));
+        if let Some(outcome) = &self.outcome {
+            disp.push_str(&format!("Game over: {}\n", outcome.describe()));
+            if let Some(winning_line) = &self.winning_line {
+                disp.push_str(&format!("Player {}'s winning line is marked with *\n", winning_line.winner));
+            }
+        } else if let Some(offered_by) = self.draw_offered_by.as_ref().and_then(|offered_by| game.player_number(offered_by)) {
+            if viewer_number == Some(offered_by) {
+                disp.push_str("You have offered a draw, waiting for your opponent to respond\n");
+            } else if viewer_number.is_some() {
+                disp.push_str("Your opponent has offered a draw. Use AcceptDraw or DeclineDraw to respond\n");
+            } else {
+                disp.push_str(&format!("Player {} has offered a draw\n", offered_by));
+            }
         }
 
         disp
     }
 
-    pub fn evolve(&self, game: Game, next_move: &Move) -> GameState {
+    pub fn evolve(&self, game: &Game, next_move: &Move<MoveType>) -> GameState {
         // given a current state, a game and a move, compute the next state
         // You can assume all moves are valid
+        let mut next_state = self.clone();
+        next_state.apply(game, next_move);
+        next_state
+    }
 
-        // Prepare variables for evolution
-        let mut grid = self.grid.clone();
-        let mut moves_history = self.moves_history.clone();
-        let mut player_1 = self.player_1.clone();
-        let mut player_2 = self.player_2.clone();
-        let in_progress: bool;
+    /// Evolves the state in place, which avoids copying the grid and history when replaying a whole game
+    pub fn apply(&mut self, game: &Game, next_move: &Move<MoveType>) {
+        // Moves reaching here have been validated, so are always made by one of the players
+        let player = match game.player_number(&next_move.author) {
+            Some(player) => player,
+            None => return,
+        };
 
         // Record move
-        moves_history.push(next_move.clone());
+        self.moves_history.push(next_move.clone());
 
         // Mutate state
         match next_move.move_type {
             MoveType::DropPiece{column} => {
-                if game.player_1 == next_move.author {
-                    grid = self.drop_piece(grid, 1, column as usize);
-                    // Check for player win and force opponent to resign if so
-                    player_2.resigned = self.check_win(grid, 1);
-                } else {
-                    grid = self.drop_piece(grid, 2, column as usize);
-                    // Check for player win and force opponent to resign if so
-                    player_1.resigned = self.check_win(grid, 2);
-                }
-
-                // Check for finish
-                if player_1.resigned || player_2.resigned {
-                    in_progress = false;
+                self.drop_piece(player, column as usize);
+                self.outcome = self.check_finish(game, player);
+            },
+            MoveType::PopOut{column} => {
+                self.pop_piece(column as usize);
+                self.outcome = self.check_finish(game, player);
+            },
+            MoveType::Resign => {
+                if player == 1 {
+                    self.player_1.resigned = true;
                 } else {
-                    // If draw, must stop progression
-                    in_progress = !self.check_draw(grid);
+                    self.player_2.resigned = true;
                 }
+                self.outcome = Some(GameOutcome::Resigned{winner: opponent_of(player)});
+            },
+            MoveType::OfferDraw => {
+                self.draw_offered_by = Some(next_move.author.clone());
+            },
+            MoveType::AcceptDraw => {
+                self.draw_offered_by = None;
+                self.outcome = Some(GameOutcome::DrawAgreed);
+            },
+            MoveType::DeclineDraw => {
+                self.draw_offered_by = None;
+            },
+            MoveType::ClaimTimeout => {
+                self.outcome = Some(GameOutcome::Timeout{winner: player});
             }
         }
 
-        // Return mutated state
-        GameState {
-            grid,
-            moves_history,
-            player_1,
-            player_2,
-            in_progress,
+        if next_move.move_type.takes_turn() {
+            self.clock.record_turn(&game.time_control, player, next_move.timestamp);
         }
-    }
-
-    fn drop_piece(&self, mut grid: Grid, player: u8, column: usize) -> Grid {
-        // Get column as grid slice
-        let col_array = &grid[column];
 
-        // Make gravity happen
-        for el in 0..(col_array.len()) {  // begin at bottom of column and work upwards
-            if col_array[el] == 0 {         // until unoccupied spot
-                grid[column][el as usize] = player;
-                break;
-            }
+        // Playing on instead of answering a draw offer declines it
+        if next_move.move_type.takes_turn() && self.draw_offered_by.as_ref().map_or(false, |offered_by| *offered_by != next_move.author) {
+            self.draw_offered_by = None;
         }
 
-        grid
+        self.in_progress = self.outcome.is_none();
     }
 
-    fn check_draw(&self, grid: Grid) -> bool {
-        // If all columns full
-        // This is called if neither player has resigned after a drop
-        for col in 0..(COLUMNS) {
-            // check if vacant spot in top row
-            if grid[col][ROWS-1] == 0 {
-                return false;
-            }
-        }
-
-        return true;
+    /// The bitboard mirroring the grid. States loaded from JSON rebuild it on first use.
+    fn bitboard(&mut self) -> &mut Bitboard {
+        let grid = &self.grid;
+        self.bitboard.get_or_insert_with(|| Bitboard::from_grid(grid))
     }
 
-    fn check_win(&self, grid: Grid, player: u8) -> bool {    
-        self.check_column_win(grid, player) || 
-        self.check_row_win(grid, player) || 
-        self.check_diagonal_win(grid, player)
-    }
+    fn drop_piece(&mut self, player: u8, column: usize) {
+        // Update the bitboard first, in case it needs rebuilding from the grid
+        self.bitboard().drop_piece(player, column);
 
-    fn check_column_win(&self, grid: Grid, player: u8) -> bool {
-        for col in 0..(grid.len()) {
-            let mut count = 0;
-
-            for row in 0..(grid[col].len()) {
-                // check if piece is the player's
-                if grid[col][row] == player {
-                    // then increment counter
-                    count = count + 1;
-
-                    // connect 4
-                    if count == 4 {
-                        return true;
-                    }
-
-                } else { 
-                    // reset counter
-                    count = 0;
-                }
-            }
+        // Make gravity happen
+        let col_array = &mut self.grid[column];
+        if let Some(el) = col_array.iter().position(|&piece| piece == 0) { // lowest unoccupied spot
+            col_array[el] = player;
         }
+    }
+
+    fn pop_piece(&mut self, column: usize) {
+        self.bitboard().pop_piece(column);
 
-        return false;
+        // Remove the bottom piece and let the rest of the column fall down by one
+        let col_array = &mut self.grid[column];
+        col_array.remove(0);
+        col_array.push(0);
     }
 
-    fn check_row_win(&self, grid: Grid, player: u8) -> bool {
-        for row in 0..(grid[0].len()) {
-            let mut count = 0;
+    fn check_finish(&mut self, game: &Game, player: u8) -> Option<GameOutcome> {
+        let win_length = game.win_length as usize;
+        let opponent = opponent_of(player);
+        let bitboard = self.bitboard();
 
-            for col in 0..(grid.len()) {
-                // check if piece is the player's
-                if grid[col][row] == player {
-                    // then increment counter
-                    count = count + 1;
-
-                    // connect 4!
-                    if count == 4 {
-                        return true;
-                    }
-
-                } else { // reset counter
-                    count = 0;
-                }
-            }
+        // A pop can complete lines for both players at once, in which case the player who popped wins
+        let winner = if bitboard.has_line(player, win_length) {
+            Some(player)
+        } else if bitboard.has_line(opponent, win_length) {
+            Some(opponent)
+        } else {
+            None
+        };
+
+        match winner {
+            Some(winner) => {
+                let cells = bitboard.line_cells(winner, win_length);
+                self.winning_line = Some(WinningLine{winner, cells});
+                Some(GameOutcome::Connected{winner})
+            },
+            // If draw, must stop progression
+            None if bitboard.is_full() && is_stuck(game, bitboard, opponent) => Some(GameOutcome::BoardFull),
+            None => None,
         }
-
-        return false;
     }
 
-    fn check_diagonal_win(&self, grid: Grid, player: u8) -> bool {
-        // Up and to the right diagonal
-        for row in 0..=2 {
-            for col in 0..=3 {
-                if 
-                    grid[col][row]      == player &&
-                    grid[col+1][row+1]  == player &&
-                    grid[col+2][row+2]  == player &&
-                    grid[col+3][row+3]  == player
-                    {
-                        return true;
-                    }
-            }
+    fn is_in_winning_line(&self, col: usize, row: usize) -> bool {
+        self.winning_line.as_ref().map_or(false, |line| line.cells.contains(&(col as u32, row as u32)))
+    }
 
-        // Down and to the right diagonal
-        for row in 3..=5 {
-            for col in 0..=3 {
-                if 
-                    grid[col][row]      == player &&
-                    grid[col+1][row-1]  == player &&
-                    grid[col+2][row-2]  == player &&
-                    grid[col+3][row-3]  == player
-                    {
-                        return true;
-                    }
-                }
-            }
+    // Validation fragment
+    pub fn is_column_in_bounds(&self, column: u32) -> Result<(), GameError> {
+        if (column as usize) < self.grid.len() {
+            Ok(())
+        } else {
+            Err(GameError::ColumnOutOfBounds{column})
         }
-        return false;
     }
 
     // Validation fragment
-    pub fn is_column_in_bounds(&self, column: u32) -> Result<(), String> {
-        if column < (COLUMNS) as u32 {
+    pub fn is_bottom_piece_players(&self, player: u8, column: u32) -> Result<(), GameError> {
+        if self.grid[column as usize][0] == player {
             Ok(())
         } else {
-            Err("Column out of bounds".into())
+            Err(GameError::illegal_move("Can only pop out your own piece from the bottom of a column"))
         }
     }
 
     // Validation fragment
-    pub fn is_column_not_full(&self, game_state: &GameState, column: u32) -> Result<(), String> {
+    pub fn is_column_not_full(&self, game_state: &GameState, column: u32) -> Result<(), GameError> {
         // If the top row of the column is 0, then it can receive a piece
-        if game_state.grid[column as usize][ROWS-1] == 0 {
+        let cells = &game_state.grid[column as usize];
+        if cells[cells.len()-1] == 0 {
             Ok(())
         } else {
-            Err("Column is full".into())
+            Err(GameError::ColumnFull{column})
         }
     }
 }
+
+/// On a full board the next player can only move in PopOut, and only if they have a piece to pop
+fn is_stuck(game: &Game, bitboard: &Bitboard, next_player: u8) -> bool {
+    match game.variant {
+        Variant::Classic => true,
+        Variant::PopOut => !bitboard.has_bottom_piece(next_player),
+    }
+}
+
+/// How a player's pieces are drawn on the grid
+pub fn piece_symbol(player: u8) -> &'static str {
+    if player == 1 { "1" } else { "2" }
+}
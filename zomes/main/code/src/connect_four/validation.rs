@@ -1,35 +1,78 @@
+use crate::error::GameError;
 use crate::game::Game;
 use crate::game_move::Move;
+use crate::game_rules::{
+    is_game_in_progress,
+    is_player_in_game,
+    is_players_turn_in_time,
+    is_valid_timeout_claim,
+};
 use super::{
     GameState,
+    Variant,
 };
 
 use hdk::holochain_persistence_api::cas::content::Address;
 use super::moves::MoveType;
 
+pub const MIN_BOARD_SIZE: u32 = 3;
+// Boards up to 10x10 fit in the 128 bit bitboards used for win detection
+pub const MAX_BOARD_SIZE: u32 = 10;
+pub const MIN_WIN_LENGTH: u32 = 3;
+
+pub fn validate_game(game: &Game) -> Result<(), String> {
+    // Check the board is not unreasonably small or large and a winning line fits on it
+    is_board_dimension_valid("width", game.board_width)?;
+    is_board_dimension_valid("height", game.board_height)?;
+    is_win_length_valid(game)?;
+    Ok(())
+}
+
 /**
  *
- * To implement your own custom rule validation all you need to do is re-implement the function `is_valid` on `Move`
+ * The Connect Four rules are validated by the function `is_valid` on `Move`
  * 
  * This function  takes the current game and the game state (which includes all the existing moves) 
  * and determines if a new candidate move is valid. Typically this will involve first matching on the move type
  * and then determining if the move is valid.
  * 
- * It function must return Ok(()) if a move is valid and Err("Some error string".into()) for an invalid move.
- * It is useful to provide descriptive error strings as these can be visible to the end user.
+ * It function must return Ok(()) if a move is valid and a GameError for an invalid move.
+ * Rules without a GameError of their own use `GameError::IllegalMove` with a descriptive reason, as these can be visible to the end user.
  *
  */
 
-impl Move {
-    pub fn is_valid(&self, game: Game, game_state: GameState) -> Result<(), String> {
+impl Move<MoveType> {
+    pub fn is_valid(&self, game: &Game, game_state: &GameState) -> Result<(), GameError> {
         // Check if a move is valid given the current game and its state
-        is_game_in_progress(&game_state)?;
-        is_it_players_turn(self.author.clone(), &game, &game_state)?;
+        is_game_in_progress(game_state)?;
 
         match self.move_type {
             MoveType::DropPiece{column} => {
+                is_players_turn_in_time(game, game_state, self)?;
+                game_state.is_column_in_bounds(column)?;
+                game_state.is_column_not_full(game_state, column)?;
+            },
+            MoveType::PopOut{column} => {
+                is_pop_out_allowed(game)?;
+                let player = is_players_turn_in_time(game, game_state, self)?;
                 game_state.is_column_in_bounds(column)?;
-                game_state.is_column_not_full(&game_state, column)?;
+                game_state.is_bottom_piece_players(player, column)?;
+            },
+            MoveType::Resign => {
+                // A player may resign at any point, whether or not it is their turn
+                is_player_in_game(game, &self.author)?;
+            },
+            MoveType::OfferDraw => {
+                is_player_in_game(game, &self.author)?;
+                is_no_draw_offer_pending(game_state)?;
+                is_first_draw_offer_this_turn(&self.author, game_state)?;
+            },
+            MoveType::AcceptDraw | MoveType::DeclineDraw => {
+                is_player_in_game(game, &self.author)?;
+                is_draw_offered_by_opponent(&self.author, game_state)?;
+            },
+            MoveType::ClaimTimeout => {
+                is_valid_timeout_claim(game, game_state, self)?;
             }
         }
 
@@ -37,32 +80,54 @@ impl Move {
     }
 }
 
-fn is_game_in_progress(game_state: &GameState) -> Result<(), String> {
-    if game_state.in_progress == false {
-        Err("Game has ended".into())
+fn is_board_dimension_valid(name: &str, size: u32) -> Result<(), String> {
+    if size < MIN_BOARD_SIZE || size > MAX_BOARD_SIZE {
+        Err(format!("Board {} must be between {} and {}", name, MIN_BOARD_SIZE, MAX_BOARD_SIZE))
     } else {
         Ok(())
     }
 }
 
-fn is_it_players_turn(player: Address, game: &Game, game_state: &GameState) -> Result<(), String> {
-    let moves_history = &game_state.moves_history;
+fn is_win_length_valid(game: &Game) -> Result<(), String> {
+    if game.win_length < MIN_WIN_LENGTH {
+        Err(format!("Win length must be at least {}", MIN_WIN_LENGTH))
+    } else if game.win_length > game.board_width && game.win_length > game.board_height {
+        Err(format!("A line of {} does not fit on a {}x{} board", game.win_length, game.board_width, game.board_height))
+    } else {
+        Ok(())
+    }
+}
 
-    match moves_history.last() {
-        Some(last_move) => {
-            if last_move.author == player {
-                Err("Not your turn, must wait for other player to make a move".into())
-            } else {
-                Ok(())
-            }
-        },
-        None => { 
-            // if no moves in history, determine who goes first
-            if game.player_2 == player {
-                Ok(())
-            } else {
-                Err("Player 2 must make the first move".into())
-            }
-        }
+fn is_pop_out_allowed(game: &Game) -> Result<(), GameError> {
+    match game.variant {
+        Variant::PopOut => Ok(()),
+        _ => Err(GameError::illegal_move("Pieces can only be popped out in PopOut games")),
+    }
+}
+
+fn is_no_draw_offer_pending(game_state: &GameState) -> Result<(), GameError> {
+    match game_state.draw_offered_by {
+        Some(_) => Err(GameError::illegal_move("A draw has already been offered")),
+        None => Ok(()),
+    }
+}
+
+/// A declined offer can only be made again after another turn, so players cannot keep offering a draw
+fn is_first_draw_offer_this_turn(player: &Address, game_state: &GameState) -> Result<(), GameError> {
+    let already_offered = game_state.moves_history.iter().rev()
+        .take_while(|past_move| !past_move.move_type.takes_turn())
+        .any(|past_move| past_move.move_type == MoveType::OfferDraw && past_move.author == *player);
+    if already_offered {
+        Err(GameError::illegal_move("You can only offer a draw once per turn"))
+    } else {
+        Ok(())
+    }
+}
+
+fn is_draw_offered_by_opponent(player: &Address, game_state: &GameState) -> Result<(), GameError> {
+    match &game_state.draw_offered_by {
+        Some(offered_by) if offered_by != player => Ok(()),
+        Some(_) => Err(GameError::illegal_move("Cannot respond to your own draw offer")),
+        None => Err(GameError::illegal_move("No draw has been offered")),
     }
 }
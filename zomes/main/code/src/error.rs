@@ -0,0 +1,88 @@
+use std::fmt;
+use hdk::{
+    error::ZomeApiError,
+    holochain_persistence_api::{
+        cas::content::{Address},
+    },
+    holochain_json_api::{
+        error::JsonError, json::JsonString,
+    },
+};
+
+/**
+ *
+ * GameError describes everything that can go wrong loading or playing a game.
+ *
+ * Zome functions return these inside `ZomeApiError::Internal` as JSON so clients can match on the
+ * variant, e.g. `{"Internal": "{\"ColumnFull\":{\"column\":3}}"}`. Validation callbacks can only
+ * return a string so they use the human readable message from `Display` instead.
+ *
+ */
+
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
+pub enum GameError {
+    /// Nothing is stored at the address, or it has not been gossiped to this agent yet
+    NotFound { address: Address },
+    /// The entry at the address is not of the expected type
+    WrongEntryType { address: Address, expected: String },
+    /// The entry at the address could not be read as the expected type
+    CorruptEntry { address: Address, reason: String },
+    /// A move's `previous_move` does not lead back to its game
+    BrokenMoveChain { address: Address },
+    /// The move lost a fork to another move following on from the same one, so it is not part of the game
+    NotCanonical { address: Address },
+    /// Asked for the state after more moves than the game has had
+    PlyOutOfRange { ply: usize, move_count: usize },
+    /// The game was created with rules this DNA does not know about
+    UnknownGameType { game_type: String },
+    /// The move is not one of the moves of this game
+    InvalidMoveType { reason: String },
+    GameOver,
+    NotAPlayer,
+    NotYourTurn,
+    OutOfTime,
+    ColumnOutOfBounds { column: u32 },
+    ColumnFull { column: u32 },
+    /// The move breaks some other rule of the game
+    IllegalMove { reason: String },
+}
+
+impl GameError {
+    pub fn illegal_move(reason: &str) -> Self {
+        GameError::IllegalMove{reason: reason.into()}
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::NotFound{address} => write!(f, "Could not find {}", address),
+            GameError::WrongEntryType{address, expected} => write!(f, "{} is not a {}", address, expected),
+            GameError::CorruptEntry{address, reason} => write!(f, "Could not read {}: {}", address, reason),
+            GameError::BrokenMoveChain{address} => write!(f, "Move {} does not follow on from a move in the same game", address),
+            GameError::NotCanonical{address} => write!(f, "Move {} lost a fork to another move and is not part of the game", address),
+            GameError::PlyOutOfRange{ply, move_count} => write!(f, "Cannot show move {} of a game with {} moves", ply, move_count),
+            GameError::UnknownGameType{game_type} => write!(f, "Unknown game type: {}", game_type),
+            GameError::InvalidMoveType{reason} => write!(f, "Move is not valid for this game: {}", reason),
+            GameError::GameOver => write!(f, "Game has ended"),
+            GameError::NotAPlayer => write!(f, "Only the players of this game can make this move"),
+            GameError::NotYourTurn => write!(f, "Not your turn, must wait for other player to make a move"),
+            GameError::OutOfTime => write!(f, "You have run out of time"),
+            GameError::ColumnOutOfBounds{column} => write!(f, "Column {} is out of bounds", column),
+            GameError::ColumnFull{column} => write!(f, "Column {} is full", column),
+            GameError::IllegalMove{reason} => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl From<GameError> for ZomeApiError {
+    fn from(error: GameError) -> ZomeApiError {
+        ZomeApiError::Internal(JsonString::from(error).to_string())
+    }
+}
+
+impl From<GameError> for String {
+    fn from(error: GameError) -> String {
+        error.to_string()
+    }
+}
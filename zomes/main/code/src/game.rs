@@ -1,10 +1,10 @@
 use std::convert::TryFrom;
+use std::fmt::Debug;
 use hdk::{
-    utils,
     entry_definition::ValidatingEntryType,
-    error::{ZomeApiResult, ZomeApiError},
+    error::ZomeApiResult,
     holochain_persistence_api::{
-        cas::content::{AddressableContent, Address},
+        cas::content::{Address},
     },
     holochain_json_api::{
         error::JsonError, json::JsonString,
@@ -13,126 +13,556 @@ use hdk::{
         dna::entry_types::Sharing,
         validation::EntryValidationData,
         entry::Entry,
-        link::LinkMatch,
+        link::{LinkMatch, link_data::LinkData},
     }
 };
 
+use serde_json::Value as JsonValue;
+
+use crate::checkpoint;
+use crate::clock::{self, TimeControl};
+use crate::checkers;
+use crate::connect_four::{self, Variant};
+use crate::error::GameError;
 use crate::game_move::Move;
-use crate::GameState;
+use crate::game_rules::GameRules;
+use crate::matchmaking::GetResponse;
+
+pub const DEFAULT_BOARD_WIDTH: u32 = 7;
+pub const DEFAULT_BOARD_HEIGHT: u32 = 6;
+pub const DEFAULT_WIN_LENGTH: u32 = 4;
+
+/// Games are linked from both players' agent addresses with this link type when they are created
+const ACTIVE_GAME_LINK_TYPE: &str = "active_game";
+/// and with this one, tagged with the final move, when they end
+const FINISHED_GAME_LINK_TYPE: &str = "finished_game";
 
 #[derive(Clone, Debug, Serialize, Deserialize, DefaultJson)]
 pub struct Game {
+    #[serde(default = "default_game_type")]
+    pub game_type: String,
     pub player_1: Address,
     pub player_2: Address,
     pub created_at: u32,
+    #[serde(default = "default_board_width")]
+    pub board_width: u32,
+    #[serde(default = "default_board_height")]
+    pub board_height: u32,
+    #[serde(default = "default_win_length")]
+    pub win_length: u32,
+    #[serde(default)]
+    pub variant: Variant,
+    #[serde(default)]
+    pub time_control: TimeControl,
+}
+
+/// Two or more moves that follow on from the same move. Only one of them can be part of the game.
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson)]
+pub struct Fork {
+    /// The game or move that the competing moves follow on from
+    pub previous_move: Address,
+    /// How many moves into the game the fork happened
+    pub ply: usize,
+    /// Every move linked from `previous_move`
+    pub candidates: Vec<Address>,
+    /// The move the game continues with, if any of the candidates were valid
+    pub chosen: Option<Address>,
+}
+
+/// The moves of a game in order with their addresses, along with any forks found while following them
+#[derive(Clone, Debug)]
+pub struct MoveChain<M> {
+    pub moves: Vec<Move<M>>,
+    pub addresses: Vec<Address>,
+    pub forks: Vec<Fork>,
+}
+
+impl<M> MoveChain<M> {
+    pub fn new() -> Self {
+        MoveChain {
+            moves: Vec::new(),
+            addresses: Vec::new(),
+            forks: Vec::new(),
+        }
+    }
+}
+
+/// How far into a game a client polling for new moves has already seen
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson)]
+pub enum Since {
+    /// The address of the last move the client has seen, or of the game if it has seen none.
+    /// If the move has since lost a fork the client is sent a `GameError::NotCanonical` and should start again.
+    LastMove(Address),
+    /// The number of moves the client has seen
+    Ply(usize),
+}
+
+/// A compact summary of where a game is up to, so clients do not need to load the full state
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
+pub struct GameStatus {
+    pub in_progress: bool,
+    /// The number of the player whose turn it is, or None once the game is over
+    pub current_player: Option<u8>,
+    /// The number of the player who won, if the game is over and was not drawn
+    pub winner: Option<u8>,
+    /// How the game ended, in words
+    pub outcome: Option<String>,
+}
+
+/// The details of a game that lists of games need, without the moves that make up its state
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson)]
+pub struct GameSummary {
+    pub game_type: String,
+    pub player_1: Address,
+    pub player_2: Address,
+    /// The player who makes the first move
+    pub first_player: Address,
+    pub move_count: usize,
+    pub status: GameStatus,
+    /// The player whose turn it is, or None once the game is over
+    pub current_player_address: Option<Address>,
+    pub created_at: u32,
+    /// The time of the latest move, or None if no moves have been made
+    pub last_move_at: Option<u32>,
+}
+
+/// The games an agent is playing and has played, most recently created first
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson)]
+pub struct PlayerGames {
+    pub active: Vec<GetResponse<GameSummary>>,
+    pub finished: Vec<GetResponse<GameSummary>>,
+}
+
+/// The moves made in a game since a client last polled it
+#[derive(Clone, Debug, Serialize)]
+pub struct StateUpdate<M> {
+    pub moves: Vec<Move<M>>,
+    pub addresses: Vec<Address>,
+    /// The latest move of the game, to poll from next time
+    pub last_move: Address,
+    pub ply: usize,
+    pub status: GameStatus,
+}
+
+/// The state of a game after a particular move, along with where that move is in the game
+#[derive(Clone, Debug)]
+pub struct Snapshot<R> {
+    pub state: R,
+    /// The address of the latest move, or of the game if no moves have been made
+    pub last_move: Address,
+    /// How many moves have been made
+    pub ply: usize,
+    /// The time of the latest move, or when the game was created
+    pub last_timestamp: u32,
+}
+
+impl<R: GameRules> Snapshot<R> {
+    pub fn initial(game_address: &Address, game: &Game) -> Self {
+        Snapshot {
+            state: R::initial(game),
+            last_move: game_address.clone(),
+            ply: 0,
+            last_timestamp: game.created_at,
+        }
+    }
+
+    /// Moves the snapshot on by a move that has been validated
+    pub fn advance(&mut self, game: &Game, address: &Address, next_move: &Move<R::MoveType>) {
+        self.state.apply(game, next_move);
+        self.last_move = address.clone();
+        self.ply += 1;
+        self.last_timestamp = next_move.timestamp;
+    }
+}
+
+/// Settings that can be chosen when a game is created. Any left out fall back to the classic game.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, DefaultJson)]
+pub struct GameOptions {
+    pub game_type: Option<String>,
+    pub board_width: Option<u32>,
+    pub board_height: Option<u32>,
+    pub win_length: Option<u32>,
+    pub variant: Option<Variant>,
+    pub time_control: Option<TimeControl>,
+}
+
+impl Game {
+    pub fn new(player_1: Address, player_2: Address, created_at: u32, options: GameOptions) -> Self {
+        let game_type = options.game_type.unwrap_or_else(default_game_type);
+        let (board_width, board_height, win_length) = default_board(&game_type);
+        Game {
+            game_type,
+            player_1,
+            player_2,
+            created_at,
+            board_width: options.board_width.unwrap_or(board_width),
+            board_height: options.board_height.unwrap_or(board_height),
+            win_length: options.win_length.unwrap_or(win_length),
+            variant: options.variant.unwrap_or_default(),
+            time_control: options.time_control.unwrap_or_default(),
+        }
+    }
+
+    /// Player 1 is the game creator and player 2 moves first. None if the agent is not playing.
+    pub fn player_number(&self, agent: &Address) -> Option<u8> {
+        if self.player_1 == *agent {
+            Some(1)
+        } else if self.player_2 == *agent {
+            Some(2)
+        } else {
+            None
+        }
+    }
+
+    /// The address of player 1 or player 2
+    pub fn player_address(&self, player: u8) -> &Address {
+        if player == 1 { &self.player_1 } else { &self.player_2 }
+    }
+
+    /// Player 2 always makes the first move
+    pub fn first_player(&self) -> &Address {
+        self.player_address(2)
+    }
+
+    /// Whether an agent is one of the two players in this game
+    pub fn is_player(&self, agent: &Address) -> bool {
+        self.player_1 == *agent || self.player_2 == *agent
+    }
+}
+
+/// Player 1 and player 2 take turns, so each is the other's opponent
+pub fn opponent_of(player: u8) -> u8 {
+    if player == 1 { 2 } else { 1 }
+}
+
+fn default_game_type() -> String {
+    connect_four::GAME_TYPE.to_string()
+}
+
+/// The board width, board height and win length of a game when they are not chosen.
+/// Checkers is always played on an 8x8 board and has no winning line.
+fn default_board(game_type: &str) -> (u32, u32, u32) {
+    if game_type == checkers::GAME_TYPE {
+        (checkers::BOARD_SIZE, checkers::BOARD_SIZE, 0)
+    } else {
+        (DEFAULT_BOARD_WIDTH, DEFAULT_BOARD_HEIGHT, DEFAULT_WIN_LENGTH)
+    }
+}
+
+fn default_board_width() -> u32 {
+    DEFAULT_BOARD_WIDTH
+}
+
+fn default_board_height() -> u32 {
+    DEFAULT_BOARD_HEIGHT
+}
+
+fn default_win_length() -> u32 {
+    DEFAULT_WIN_LENGTH
 }
 
 /*=====================================
 =            DHT Functions            =
 =====================================*/
 
-/// Traverse the linked list rooted at a game to find all the moves
-pub fn get_moves(game_address: &Address) -> ZomeApiResult<Vec<Move>> {
-    match hdk::get_links(game_address, LinkMatch::Any, LinkMatch::Any)?.addresses().into_iter().next() {
-        Some(first_move) => {
-            let mut move_addresses = vec![first_move];
-            let mut more = true;
-            while more {
-                more = match hdk::get_links(move_addresses.last().unwrap(), LinkMatch::Any, LinkMatch::Any)?.addresses().into_iter().next() {
-                    Some(addr) => {
-                        move_addresses.push(addr.clone());
-                        true
-                    },
-                    None => {
-                        false
-                    },
-                }
-            }
-            let moves: Vec<Move> = move_addresses.iter().map(|addr| {
-                let move_entry = hdk::get_entry(addr).unwrap().unwrap();
-                if let Entry::App(_, move_struct) = move_entry {
-                    Move::try_from(move_struct).expect("Entry at address is type other than Move")
+/**
+ * Follows the links from a game through each of its moves in turn.
+ *
+ * Normally each move is linked from exactly one previous move. Validation stops a player making two moves
+ * from the same one, but the other player can race them with a move that does not take a turn, like resigning.
+ * Every agent must agree on which one the game continues with, so the move by the player whose turn it was
+ * is chosen. Neither player can change this by picking their move's timestamp. Wherever this happens a Fork is recorded.
+ */
+pub fn get_move_chain<R: GameRules>(game_address: &Address, game: &Game) -> ZomeApiResult<MoveChain<R::MoveType>> {
+    let mut chain = MoveChain::new();
+    follow_moves(game_address, game, Snapshot::initial(game_address, game), &mut chain)?;
+    Ok(chain)
+}
+
+/// Follows the move chain on from `snapshot`, adding the moves found to `chain`, and returns the latest snapshot
+fn follow_moves<R: GameRules>(game_address: &Address, game: &Game, mut snapshot: Snapshot<R>, chain: &mut MoveChain<R::MoveType>) -> ZomeApiResult<Snapshot<R>> {
+    loop {
+        let player_to_move = snapshot.state.player_to_move(game);
+        let candidates = hdk::get_links(&snapshot.last_move, LinkMatch::Exactly(""), LinkMatch::Any)?.addresses();
+        let mut valid_moves: Vec<(Address, Move<R::MoveType>)> = candidates.iter()
+            .filter_map(|address| {
+                // Moves that cannot be read yet are skipped, as an invalid move would be
+                let candidate = get_entry_as::<Move<JsonValue>>(address, "move").ok()?
+                    .parse::<R::MoveType>().ok()?;
+                let follows_on = candidate.game == *game_address && candidate.previous_move == snapshot.last_move;
+                if follows_on && snapshot.state.is_valid(game, &candidate).is_ok() {
+                    Some((address.clone(), candidate))
                 } else {
-                    panic!("Not an app entry!")
+                    None
                 }
-            }).collect();
-            Ok(moves)
-        },
-        None => {
-            Ok(Vec::new())
+            })
+            .collect();
+        // Validation allows each player only one move here, so the player whose turn it was decides the fork
+        valid_moves.sort_by_key(|(address, candidate)| {
+            (game.player_number(&candidate.author) != Some(player_to_move), address.to_string())
+        });
+        let chosen = valid_moves.into_iter().next();
+
+        if candidates.len() > 1 {
+            chain.forks.push(Fork {
+                previous_move: snapshot.last_move.clone(),
+                ply: snapshot.ply,
+                candidates: candidates.clone(),
+                chosen: chosen.as_ref().map(|(address, _)| address.clone()),
+            });
+        }
+
+        match chosen {
+            Some((address, next_move)) => {
+                snapshot.advance(game, &address, &next_move);
+                chain.addresses.push(address);
+                chain.moves.push(next_move);
+            },
+            None => return Ok(snapshot),
         }
     }
 }
 
-pub fn get_state(game_address: &Address) -> ZomeApiResult<GameState> {
-    let moves = get_moves(game_address)?;
-    let game = get_game(game_address)?;
-    let new_state = moves.iter().fold(GameState::initial(), |state, new_move| state.evolve(game.clone(), new_move));
-    Ok(new_state)
+pub fn get_moves<R: GameRules>(game_address: &Address, game: &Game) -> ZomeApiResult<Vec<Move<R::MoveType>>> {
+    get_move_chain::<R>(game_address, game).map(|chain| chain.moves)
 }
 
-pub fn get_game(game_address: &Address) -> ZomeApiResult<Game> {
-    utils::get_as_type(game_address.to_owned())
+/// The latest snapshot of a game, carrying on from its most recent checkpoint
+pub fn get_latest_snapshot<R: GameRules>(game_address: &Address, game: &Game) -> ZomeApiResult<Snapshot<R>> {
+    let start = checkpoint::get_latest_checkpoint(game_address, usize::max_value())?
+        .unwrap_or_else(|| Snapshot::initial(game_address, game));
+    follow_moves(game_address, game, start, &mut MoveChain::new())
 }
 
-/*=====  End of DHT Functions  ======*/
+/// The snapshot of a game just after `last_move`, found by following each `previous_move` back to the nearest
+/// checkpoint or the game itself. Validation uses this as the links between moves may not have been published yet.
+pub fn get_snapshot_at<R: GameRules>(game_address: &Address, game: &Game, last_move: &Address) -> ZomeApiResult<Snapshot<R>> {
+    replay_to(game_address, game, last_move, usize::max_value())
+}
 
+/// As `get_snapshot_at`, but only starting from checkpoints made before `checkpoints_before` moves
+pub fn replay_to<R: GameRules>(game_address: &Address, game: &Game, last_move: &Address, checkpoints_before: usize) -> ZomeApiResult<Snapshot<R>> {
+    let checkpoints = checkpoint::get_checkpoint_addresses(game_address, checkpoints_before)?;
+    let mut moves = Vec::new();
+    let mut address = last_move.clone();
+    let start = loop {
+        if address == *game_address {
+            break Snapshot::initial(game_address, game)
+        }
+        if let Some(checkpoint) = checkpoints.get(&address).and_then(|checkpoint_address| checkpoint::load_checkpoint::<R>(checkpoint_address).ok()) {
+            break checkpoint
+        }
+        let game_move: Move<JsonValue> = get_entry_as(&address, "move")?;
+        if game_move.game != *game_address {
+            return Err(GameError::BrokenMoveChain{address}.into())
+        }
+        let previous_move = game_move.previous_move.clone();
+        moves.push((address, game_move.parse::<R::MoveType>()?));
+        address = previous_move;
+    };
+
+    Ok(moves.iter().rev().fold(start, |mut snapshot, (address, next_move)| {
+        snapshot.advance(game, address, next_move);
+        snapshot
+    }))
+}
 
+/// How many moves into a game `last_move` was made. Each `previous_move` is followed back to the nearest
+/// checkpoint or the game itself, without replaying any of the moves.
+fn get_ply_of(game_address: &Address, last_move: &Address) -> ZomeApiResult<usize> {
+    let checkpoint_plies = checkpoint::get_checkpoint_plies(game_address)?;
+    let mut address = last_move.clone();
+    let mut moves_since = 0;
+    loop {
+        if address == *game_address {
+            return Ok(moves_since)
+        }
+        if let Some(ply) = checkpoint_plies.get(&address) {
+            return Ok(ply + moves_since)
+        }
+        let game_move: Move<JsonValue> = get_entry_as(&address, "move")?;
+        if game_move.game != *game_address {
+            return Err(GameError::BrokenMoveChain{address}.into())
+        }
+        address = game_move.previous_move;
+        moves_since += 1;
+    }
+}
 
-/*=============================================
-=            Local chain functions            =
-=============================================*/
+/// The moves made since a client last saw a game and where the game is now up to. Only the moves after the
+/// nearest checkpoint before the client's position are fetched, so polling stays cheap in long games.
+pub fn get_state_since<R: GameRules>(game_address: &Address, game: &Game, since: &Since) -> ZomeApiResult<StateUpdate<R::MoveType>> {
+    // A last move is turned into a number of moves, so the update follows the same branch as every other reader
+    let (ply, last_seen) = match since {
+        Since::LastMove(last_move) => (get_ply_of(game_address, last_move)?, Some(last_move)),
+        Since::Ply(ply) => (*ply, None),
+    };
+    let start = checkpoint::get_latest_checkpoint(game_address, ply)?
+        .unwrap_or_else(|| Snapshot::initial(game_address, game));
+    let already_seen = ply - start.ply;
+    let start_move = start.last_move.clone();
 
-pub fn get_game_local_chain(local_chain: Vec<Entry>, game_address: &Address) -> ZomeApiResult<Game> {
-    local_chain
-        .iter()
-        .filter(|entry| {
-            entry.address() == game_address.to_owned()
-        })
-        .filter_map(|entry| {
-            if let Entry::App(_, entry_data) = entry {
-                Some(Game::try_from(entry_data.clone()).unwrap())
-            } else {
-                None
-            }
-        })
-        .next()
-        .ok_or(ZomeApiError::HashNotFound)
+    let mut chain = MoveChain::new();
+    let latest = follow_moves(game_address, game, start, &mut chain)?;
+    if let Some(last_seen) = last_seen {
+        let canonical_move = if already_seen == 0 { Some(&start_move) } else { chain.addresses.get(already_seen - 1) };
+        if canonical_move != Some(last_seen) {
+            return Err(GameError::NotCanonical{address: last_seen.clone()}.into())
+        }
+    }
+    let already_seen = already_seen.min(chain.moves.len());
+    Ok(StateUpdate {
+        moves: chain.moves.split_off(already_seen),
+        addresses: chain.addresses.split_off(already_seen),
+        status: latest.state.status(game),
+        last_move: latest.last_move,
+        ply: latest.ply,
+    })
 }
 
-pub fn get_moves_local_chain(local_chain: Vec<Entry>, game_address: &Address) -> ZomeApiResult<Vec<Move>> {
-    Ok(local_chain
-        .iter()
-        .filter_map(|entry| {
-            if let Entry::App(entry_type, entry_data) = entry {
-                if entry_type.to_string() == "move" {
-                    Some(Move::try_from(entry_data.clone()).unwrap())
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        })
-        .filter(|game_move| {
-            game_move.game == game_address.to_owned()
+/// The state of a game as it was after its first `ply` moves, for reviewing games after they are played
+pub fn get_state_at_ply<R: GameRules>(game_address: &Address, game: &Game, ply: usize) -> ZomeApiResult<R> {
+    let moves = get_moves::<R>(game_address, game)?;
+    if ply > moves.len() {
+        return Err(GameError::PlyOutOfRange{ply, move_count: moves.len()}.into())
+    }
+    Ok(moves.iter().take(ply).fold(R::initial(game), |state, next_move| state.evolve(game, next_move)))
+}
+
+pub fn get_state<R: GameRules>(game_address: &Address, game: &Game) -> ZomeApiResult<R> {
+    get_latest_snapshot::<R>(game_address, game).map(|snapshot| snapshot.state)
+}
+
+/// Summarises a game from its latest snapshot
+pub fn get_game_summary(game_address: &Address) -> ZomeApiResult<GameSummary> {
+    let game = get_game(game_address)?;
+    with_game_rules!(game.game_type, R => {
+        let snapshot = get_latest_snapshot::<R>(game_address, &game)?;
+        let status = snapshot.state.status(&game);
+        Ok(GameSummary {
+            game_type: game.game_type.clone(),
+            player_1: game.player_1.clone(),
+            player_2: game.player_2.clone(),
+            first_player: game.first_player().clone(),
+            move_count: snapshot.ply,
+            current_player_address: status.current_player.map(|player| game.player_address(player).clone()),
+            status,
+            created_at: game.created_at,
+            last_move_at: if snapshot.ply > 0 { Some(snapshot.last_timestamp) } else { None },
         })
-        .rev()
-        .collect())
+    })
+}
+
+/// Links a new game from both of its players so they can find it again
+pub fn link_from_players(game_address: &Address, game: &Game) -> ZomeApiResult<()> {
+    for player in &[&game.player_1, &game.player_2] {
+        hdk::link_entries(player, game_address, ACTIVE_GAME_LINK_TYPE, "")?;
+    }
+    Ok(())
+}
+
+/// Links a game that has just ended from both of its players.
+/// The active links are left in place, as links cannot yet be reliably removed.
+pub fn link_finished_from_players(game_address: &Address, game: &Game, final_move: &Address) -> ZomeApiResult<()> {
+    for player in &[&game.player_1, &game.player_2] {
+        hdk::link_entries(player, game_address, FINISHED_GAME_LINK_TYPE, &final_move.to_string())?;
+    }
+    Ok(())
 }
 
-pub fn get_state_local_chain(local_chain: Vec<Entry>, game_address: &Address) -> ZomeApiResult<GameState> {
-    let moves = get_moves_local_chain(local_chain.clone(), game_address)?;
-    let game = get_game_local_chain(local_chain, game_address)?;
-    let new_state = moves.iter().fold(GameState::initial(), move |state, new_move| state.evolve(game.clone(), new_move));
-    Ok(new_state)
+pub fn get_games_of(agent: &Address) -> ZomeApiResult<PlayerGames> {
+    let mut finished = hdk::get_links(agent, LinkMatch::Exactly(FINISHED_GAME_LINK_TYPE), LinkMatch::Any)?.addresses();
+    finished.sort_by_key(|address| address.to_string());
+    finished.dedup();
+    let active = hdk::get_links(agent, LinkMatch::Exactly(ACTIVE_GAME_LINK_TYPE), LinkMatch::Any)?
+        .addresses()
+        .into_iter()
+        .filter(|address| !finished.contains(address))
+        .collect();
+    Ok(PlayerGames {
+        active: summarise_games(active),
+        finished: summarise_games(finished),
+    })
 }
 
+fn summarise_games(addresses: Vec<Address>) -> Vec<GetResponse<GameSummary>> {
+    let mut summaries: Vec<GetResponse<GameSummary>> = addresses.into_iter()
+        // Games that have not been gossiped to this agent yet are left out rather than failing the whole list
+        .filter_map(|address| get_game_summary(&address).ok().map(|entry| GetResponse{entry, address}))
+        .collect();
+    summaries.sort_by_key(|summary| std::cmp::Reverse(summary.entry.created_at));
+    summaries
+}
+
+/// Render the current state of a game from the point of view of `viewer`, or for a spectator
+pub fn render_state(game_address: &Address, viewer: Option<&Address>) -> ZomeApiResult<String> {
+    let game = get_game(game_address)?;
+    with_game_rules!(game.game_type, R => {
+        get_state::<R>(game_address, &game).map(|state| state.render(&game, viewer))
+    })
+}
+
+/// Render a game as it was after its first `ply` moves
+pub fn render_state_at_ply(game_address: &Address, ply: usize, viewer: Option<&Address>) -> ZomeApiResult<String> {
+    let game = get_game(game_address)?;
+    with_game_rules!(game.game_type, R => {
+        get_state_at_ply::<R>(game_address, &game, ply).map(|state| state.render(&game, viewer))
+    })
+}
+
+pub fn get_game(game_address: &Address) -> ZomeApiResult<Game> {
+    Ok(get_entry_as(game_address, "game")?)
+}
+
+/// Loads an app entry of a particular type, describing exactly what went wrong if it can't
+pub fn get_entry_as<T>(address: &Address, entry_type: &str) -> Result<T, GameError>
+where
+    T: TryFrom<JsonString>,
+    T::Error: Debug,
+{
+    match hdk::get_entry(address) {
+        Ok(Some(Entry::App(app_type, entry_data))) => {
+            if app_type.to_string() != entry_type {
+                return Err(GameError::WrongEntryType{address: address.clone(), expected: entry_type.into()})
+            }
+            T::try_from(entry_data)
+                .map_err(|e| GameError::CorruptEntry{address: address.clone(), reason: format!("{:?}", e)})
+        },
+        Ok(Some(_)) => Err(GameError::WrongEntryType{address: address.clone(), expected: entry_type.into()}),
+        Ok(None) | Err(_) => Err(GameError::NotFound{address: address.clone()}),
+    }
+}
+
+/*=====  End of DHT Functions  ======*/
+
 
-/*=====  End of Local chain functions  ======*/
 
 
+/// Games can only be linked from the agent addresses of their own players
+fn validate_player_link(link: &LinkData) -> Result<Game, String> {
+    let game: Game = get_entry_as(link.link().target(), "game")?;
+    if !game.is_player(link.link().base()) {
+        return Err("Games can only be linked from their players".into())
+    }
+    Ok(game)
+}
 
+/// A game is only linked as finished from a move that ended it
+fn validate_finished_link<R: GameRules>(game_address: &Address, game: &Game, final_move: &Address) -> Result<(), String> {
+    let snapshot = get_snapshot_at::<R>(game_address, game, final_move)
+        .map_err(|_| "Finished games must be tagged with a move in the game")?;
+    if snapshot.state.status(game).in_progress {
+        Err("Game has not finished".into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Agent addresses are the agent's public key encoded as a 63 character string starting with HcS
+fn is_agent_address(address: &Address) -> bool {
+    let address = address.to_string();
+    address.len() == 63 && address.starts_with("HcS") && address.chars().all(|c| c.is_ascii_alphanumeric())
+}
 
 pub fn definition() -> ValidatingEntryType {
     entry!(
@@ -145,17 +575,70 @@ pub fn definition() -> ValidatingEntryType {
 
         validation: | validation_data: hdk::EntryValidationData<Game>| {
             match validation_data {
-                EntryValidationData::Create{entry, validation_data: _} => {
+                EntryValidationData::Create{entry, validation_data} => {
                     let game = entry as Game;
                     if game.player_1 == game.player_2 {
                         return Err("Player 1 and Player 2 must be different agents.".into())
                     }
-                    Ok(())
+                    if !is_agent_address(&game.player_1) || !is_agent_address(&game.player_2) {
+                        return Err("Players must be agent addresses".into())
+                    }
+                    // Stops agents creating games between other people
+                    if !validation_data.sources().iter().any(|source| game.is_player(source)) {
+                        return Err("Games can only be created by one of their players".into())
+                    }
+                    if game.created_at < clock::EARLIEST_TIMESTAMP {
+                        return Err(format!("{} is not a valid creation time", game.created_at))
+                    }
+                    let header_time = clock::header_timestamp(&validation_data.package.chain_header)?;
+                    clock::is_not_after_header_time(game.created_at, header_time)?;
+                    with_game_rules!(game.game_type, R => R::validate_game(&game))
                 },
                 _ => {
                     Err("Cannot modify or delete a game".into())
                 }
             }
-        }
+        },
+
+        links: [
+            from!(
+                "%agent_id",
+                link_type: "active_game",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: | validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        hdk::LinkValidationData::LinkAdd{link, ..} => {
+                            validate_player_link(&link).map(|_| ())
+                        },
+                        hdk::LinkValidationData::LinkRemove{..} => {
+                            Err("Cannot remove links to games".into())
+                        }
+                    }
+                }
+            ),
+            from!(
+                "%agent_id",
+                link_type: "finished_game",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: | validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        hdk::LinkValidationData::LinkAdd{link, ..} => {
+                            let game = validate_player_link(&link)?;
+                            let final_move = Address::from(link.link().tag().clone());
+                            with_game_rules!(game.game_type, R => {
+                                validate_finished_link::<R>(link.link().target(), &game, &final_move)
+                            })
+                        },
+                        hdk::LinkValidationData::LinkRemove{..} => {
+                            Err("Cannot remove links to games".into())
+                        }
+                    }
+                }
+            )
+        ]
     )
 }
@@ -1,39 +1,254 @@
+use std::convert::TryFrom;
+use std::fmt::Debug;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value as JsonValue;
 use hdk::{
+    AGENT_ADDRESS,
     entry_definition::ValidatingEntryType,
+    error::ZomeApiResult,
     holochain_persistence_api::{
-        cas::content::{Address},
+        cas::content::{Address, AddressableContent},
     },
     holochain_json_api::{
-        error::JsonError, json::JsonString,
+        error::JsonError, json::{JsonString, default_to_json, default_try_from_json},
     },
     holochain_core_types::{
         dna::entry_types::Sharing,
-        validation::EntryValidationData,
+        validation::{EntryValidationData, ValidationPackage},
         entry::Entry,
     }
 };
 
-use crate::MoveType;
-use crate::game::{get_game_local_chain, get_state_local_chain};
+use crate::game;
+use crate::checkpoint;
+use crate::clock;
+use crate::error::GameError;
+use crate::game::Game;
+use crate::game_rules::GameRules;
 
 
 #[derive(Clone, Debug, Serialize, Deserialize, DefaultJson)]
 pub struct MoveInput {
 	pub game: Address,
-	pub move_type: MoveType,
+	pub move_type: JsonValue,
 	pub timestamp: u32,
 }
 
 
-#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
-pub struct Move {
+/// A move in a game. The move type is specific to the rules of the game being played.
+/// Moves are stored and validated with a raw JSON move type until the game's rules are known.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Move<M> {
 	pub game: Address,
 	pub author: Address,
-	pub move_type: MoveType,
+	pub move_type: M,
 	pub previous_move: Address,
 	pub timestamp: u32,
 }
 
+impl<M: Serialize + Debug> From<Move<M>> for JsonString {
+    fn from(game_move: Move<M>) -> JsonString {
+        default_to_json(game_move)
+    }
+}
+
+impl<M: DeserializeOwned> TryFrom<JsonString> for Move<M> {
+    type Error = JsonError;
+    fn try_from(json: JsonString) -> Result<Self, Self::Error> {
+        default_try_from_json(json)
+    }
+}
+
+impl Move<JsonValue> {
+    /// Interpret the move type according to the rules of a particular game
+    pub fn parse<M: DeserializeOwned>(self) -> Result<Move<M>, GameError> {
+        let move_type = serde_json::from_value(self.move_type)
+            .map_err(|e| GameError::InvalidMoveType{reason: e.to_string()})?;
+        Ok(Move {
+            game: self.game,
+            author: self.author,
+            move_type,
+            previous_move: self.previous_move,
+            timestamp: self.timestamp,
+        })
+    }
+}
+
+/// Whose turn it is in a game and the moves a particular player can legally make
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LegalMoves<M> {
+	pub current_player: Option<u8>,
+	pub current_player_address: Option<Address>,
+	pub player: Address,
+	pub moves: Vec<M>,
+}
+
+/// Finds the moves `player` can make next by running every candidate move through the same `is_valid`
+/// check used to validate committed moves. Candidates are checked as if they were made at `timestamp`.
+pub fn legal_moves<R: GameRules>(game_address: &Address, game: &Game, state: &R, player: &Address, timestamp: u32) -> LegalMoves<R::MoveType> {
+    let current_player = state.current_player(game);
+    let moves = match game.player_number(player) {
+        Some(player_number) => state.candidate_moves(game, player_number).into_iter()
+            .filter(|move_type| {
+                let candidate = Move {
+                    game: game_address.clone(),
+                    author: player.clone(),
+                    move_type: move_type.clone(),
+                    previous_move: game_address.clone(),
+                    timestamp,
+                };
+                state.is_valid(game, &candidate).is_ok()
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    LegalMoves {
+        current_player,
+        current_player_address: current_player.map(|number| game.player_address(number).clone()),
+        player: player.clone(),
+        moves,
+    }
+}
+
+pub fn handle_make_move<R: GameRules>(game: Game, new_move: MoveInput) -> ZomeApiResult<()> {
+    // Reject anything that is not a move in this game before committing anything
+    let move_type: R::MoveType = serde_json::from_value(new_move.move_type)
+        .map_err(|e| GameError::InvalidMoveType{reason: e.to_string()})?;
+
+    // follow the hash chain on the DHT from the latest checkpoint to find the latest move.
+    // The new move follows on from it, or from the game itself.
+    let mut snapshot = game::get_latest_snapshot::<R>(&new_move.game, &game)?;
+    let base_address = snapshot.last_move.clone();
+
+    let new_move = Move {
+        game: new_move.game,
+        author: AGENT_ADDRESS.to_string().into(),
+        move_type,
+        previous_move: base_address.clone(),
+        timestamp: new_move.timestamp,
+    };
+
+    // Check the move against the rules here as well so clients get a GameError rather than a validation message
+    snapshot.state.is_valid(&game, &new_move)?;
+    let move_entry = Entry::App(
+        "move".into(),
+        new_move.clone().into(),
+    );
+    let move_address = hdk::commit_entry(&move_entry)?;
+    hdk::link_entries(&base_address, &move_address, "", "")?;
+
+    snapshot.advance(&game, &move_address, &new_move);
+    if !snapshot.state.status(&game).in_progress {
+        game::link_finished_from_players(&new_move.game, &game, &move_address)?;
+    }
+    checkpoint::commit_if_due(&new_move.game, &snapshot)
+}
+
+/// Commits a move exactly as given, without checking it against the game or linking it into the move chain.
+/// Validation still applies, so this can not commit anything `handle_make_move` could not. It lets tests check
+/// that moves `handle_make_move` would never build, like ones on behalf of another agent, are rejected.
+pub fn commit_move(new_move: Move<JsonValue>) -> ZomeApiResult<Address> {
+    hdk::commit_entry(&Entry::App(
+        "move".into(),
+        new_move.into(),
+    ))
+}
+
+/// Only the agent who commits a move can be its author
+fn is_author_a_source(new_move: &Move<JsonValue>, sources: &[Address]) -> Result<(), String> {
+    if sources.contains(&new_move.author) {
+        Ok(())
+    } else {
+        Err("Cannot make a move on behalf of another agent".into())
+    }
+}
+
+fn validate_move<R: GameRules>(game: &Game, new_move: Move<JsonValue>, header_time: u32) -> Result<(), String> {
+    let new_move = new_move.parse::<R::MoveType>()?;
+    if !game.is_player(&new_move.author) {
+        return Err(GameError::NotAPlayer.into())
+    }
+
+    // Moves form a chain back to the game, so only the moves since the nearest checkpoint need to be fetched
+    let snapshot = game::get_snapshot_at::<R>(&new_move.game, game, &new_move.previous_move)
+        .map_err(|_| "A move must follow on from the game or an earlier move in it")?;
+
+    // Clocks rely on timestamps so they must run forwards and be close to the real time
+    clock::is_not_before(new_move.timestamp, snapshot.last_timestamp)?;
+    clock::is_close_to_header_time(new_move.timestamp, header_time, &game.time_control)?;
+
+    snapshot.state.is_valid(game, &new_move).map_err(String::from)?;
+    // The timestamp is chosen by the author, so the move must also be valid at the time it was committed.
+    // A player cannot then date a timeout claim after their opponent's time is up, or a turn before their own is.
+    let at_header_time = Move {
+        timestamp: header_time,
+        ..new_move
+    };
+    snapshot.state.is_valid(game, &at_header_time).map_err(String::from)
+}
+
+/**
+ * A player can make only one move following on from each move or the game, so they cannot take a move back
+ * by making another in its place. Validators may not have seen every link between moves yet, so the author's
+ * earlier moves are taken from the source chain in the validation package, which every validator is sent.
+ * The chain's headers are listed newest first, so the moves committed before this one come after its header.
+ */
+fn is_first_move_from_previous(package: &ValidationPackage, new_move: &Move<JsonValue>) -> Result<(), String> {
+    let (headers, entries) = match (&package.source_chain_headers, &package.source_chain_entries) {
+        (Some(headers), Some(entries)) => (headers, entries),
+        _ => return Err("Moves must be validated with their author's source chain".into()),
+    };
+    let this_header = package.chain_header.address();
+    let earlier_entries: Vec<&Address> = headers.iter()
+        .skip_while(|header| header.address() != this_header)
+        .skip(1)
+        .map(|header| header.entry_address())
+        .collect();
+
+    let already_moved = entries.iter()
+        .filter(|entry| earlier_entries.contains(&&entry.address()))
+        .filter_map(|entry| match entry {
+            Entry::App(app_type, entry_data) if app_type.to_string() == "move" => {
+                Move::<JsonValue>::try_from(entry_data.clone()).ok()
+            },
+            _ => None,
+        })
+        .any(|earlier_move| earlier_move.game == new_move.game && earlier_move.previous_move == new_move.previous_move);
+    if already_moved {
+        Err("You have already made a move following on from this one".into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Links along a game's move chain must go from a move's `previous_move` to the move itself
+fn validate_chain_link(validation_data: hdk::LinkValidationData, base_type: &str) -> Result<(), String> {
+    match validation_data {
+        hdk::LinkValidationData::LinkAdd{link, ..} => {
+            let base = link.link().base();
+            let target: Move<JsonValue> = game::get_entry_as(link.link().target(), "move")?;
+            if target.previous_move != *base {
+                return Err("A move can only be linked from the move or game it follows".into())
+            }
+            // The first move links from the game itself, later ones from a move in the same game
+            let game = if base_type == "game" {
+                base.clone()
+            } else {
+                let base_move: Move<JsonValue> = game::get_entry_as(base, "move")?;
+                base_move.game
+            };
+            if target.game != game {
+                return Err("Moves can only be linked within the same game".into())
+            }
+            Ok(())
+        },
+        hdk::LinkValidationData::LinkRemove{..} => {
+            Err("Cannot remove links between moves".into())
+        }
+    }
+}
+
 pub fn definition() -> ValidatingEntryType {
     entry!(
         name: "move",
@@ -43,26 +258,19 @@ pub fn definition() -> ValidatingEntryType {
             hdk::ValidationPackageDefinition::ChainFull
         },
 
-        validation: | validation_data: hdk::EntryValidationData<Move>| {
+        validation: | validation_data: hdk::EntryValidationData<Move<JsonValue>>| {
             match validation_data {
                 EntryValidationData::Create{entry, validation_data} => {
-                	let mut local_chain = validation_data.package.source_chain_entries
-                		.ok_or("Could not retrieve source chain")?;
-                	hdk::debug(format!("{:?}", local_chain))?;
-
-                	// load the game and game state
-                	let _new_move = Move::from(entry);
-
-                    // Sometimes the validating entry is already in the chain when validation runs,
-                    // To make our state reduction work correctly this must be removed
-                    local_chain.remove_item(&Entry::App("move".into() , _new_move.clone().into()));
-
-                	let state = get_state_local_chain(local_chain.clone(), &_new_move.game)
-                		.map_err(|_| "Could not load state during validation")?;
-                	let game = get_game_local_chain(local_chain, &_new_move.game)
-                	    .map_err(|_| "Could not load game during validation")?;
-                    
-                    _new_move.is_valid(game, state)
+                    let new_move = Move::from(entry);
+                    is_author_a_source(&new_move, &validation_data.sources())?;
+                    is_first_move_from_previous(&validation_data.package, &new_move)?;
+                    let header_time = clock::header_timestamp(&validation_data.package.chain_header)?;
+
+                    // load the game to find out which rules it is played with
+                    let game = game::get_game(&new_move.game)
+                        .map_err(|_| "Could not load game during validation")?;
+
+                    with_game_rules!(game.game_type, R => validate_move::<R>(&game, new_move, header_time))
                 },
                 _ => {
                     Err("Cannot modify or delete a move".into())
@@ -77,8 +285,8 @@ pub fn definition() -> ValidatingEntryType {
                 validation_package: || {
                     hdk::ValidationPackageDefinition::Entry
                 },
-                validation: | _validation_data: hdk::LinkValidationData| {
-                    Ok(())
+                validation: | validation_data: hdk::LinkValidationData| {
+                    validate_chain_link(validation_data, "game")
                 }
             ),
         	from!(
@@ -87,8 +295,8 @@ pub fn definition() -> ValidatingEntryType {
                 validation_package: || {
                     hdk::ValidationPackageDefinition::Entry
                 },
-                validation: | _validation_data: hdk::LinkValidationData| {
-                    Ok(())
+                validation: | validation_data: hdk::LinkValidationData| {
+                    validate_chain_link(validation_data, "move")
                 }
             )
         ]
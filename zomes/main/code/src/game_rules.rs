@@ -0,0 +1,181 @@
+use std::fmt::Debug;
+use serde::{Serialize, de::DeserializeOwned};
+use hdk::holochain_persistence_api::cas::content::Address;
+
+use crate::clock::Clock;
+use crate::error::GameError;
+use crate::game::{self, Game, GameStatus};
+use crate::game_move::Move;
+
+/**
+ *
+ * GameRules is everything the framework needs to know about a particular game.
+ *
+ * To add your own game implement this trait on your game's State struct, give the game a unique
+ * identifier and register it in `with_game_rules!` below. Game entries record the identifier so
+ * that moves, validation and rendering are always handled by the rules the game was created with.
+ *
+ */
+
+pub trait GameRules: Clone + Debug + Serialize + DeserializeOwned {
+    /// The moves that can be made in this game and the data they carry
+    type MoveType: Clone + Debug + PartialEq + Serialize + DeserializeOwned;
+
+    /// Checks the settings on a new Game entry make sense for this game
+    fn validate_game(game: &Game) -> Result<(), String>;
+
+    /// The state of a game before any moves have been made
+    fn initial(game: &Game) -> Self;
+
+    /// Computes the next state given a move. Moves passed here will always have been validated.
+    fn evolve(&self, game: &Game, next_move: &Move<Self::MoveType>) -> Self;
+
+    /// Evolves the state in place. Used when replaying games, so override it if your state is expensive to copy.
+    fn apply(&mut self, game: &Game, next_move: &Move<Self::MoveType>) {
+        *self = self.evolve(game, next_move);
+    }
+
+    /// Checks whether a candidate move is valid in the current state
+    fn is_valid(&self, game: &Game, next_move: &Move<Self::MoveType>) -> Result<(), GameError>;
+
+    /// Every move made so far, oldest first
+    fn moves_history(&self) -> &[Move<Self::MoveType>];
+
+    /// Whether the game has not ended yet
+    fn in_progress(&self) -> bool;
+
+    /// The clock, charged for each move that takes a turn
+    fn clock(&self) -> &Clock;
+
+    /// Whether a move uses up the mover's turn. Moves that do not, like resigning, can be made at any time.
+    fn takes_turn(move_type: &Self::MoveType) -> bool;
+
+    /// The most recent move that used up a turn, which determines who is to play next
+    fn last_turn_move(&self) -> Option<&Move<Self::MoveType>> {
+        self.moves_history().iter().rev().find(|m| Self::takes_turn(&m.move_type))
+    }
+
+    /// The number of the player whose turn it is. The non-creator (player 2) moves first.
+    fn player_to_move(&self, game: &Game) -> u8 {
+        self.last_turn_move()
+            .and_then(|last_move| game.player_number(&last_move.author))
+            .map_or(2, game::opponent_of)
+    }
+
+    /// The number of the player whose turn it is, or None if the game is over
+    fn current_player(&self, game: &Game) -> Option<u8> {
+        if self.in_progress() { Some(self.player_to_move(game)) } else { None }
+    }
+
+    /// Every move `player` might make in the current state. These do not need to be valid,
+    /// `game_move::legal_moves` runs each of them through `is_valid` to find the legal ones.
+    fn candidate_moves(&self, game: &Game, player: u8) -> Vec<Self::MoveType>;
+
+    /// The number of the player who won, if the game is over and was not drawn
+    fn outcome_winner(&self) -> Option<u8>;
+
+    /// How the game ended in words, if it has
+    fn outcome_description(&self) -> Option<String>;
+
+    /// Whose turn it is and how the game ended, if it has
+    fn status(&self, game: &Game) -> GameStatus {
+        GameStatus {
+            in_progress: self.in_progress(),
+            current_player: self.current_player(game),
+            winner: self.outcome_winner(),
+            outcome: self.outcome_description(),
+        }
+    }
+
+    /// How a player's pieces are shown when the game is rendered
+    fn player_symbol(player: u8) -> &'static str;
+
+    /// A human readable representation of the state for the CLI, from the point of view of `viewer`.
+    /// Spectators and agents who are not playing in the game are passed as None.
+    fn render(&self, game: &Game, viewer: Option<&Address>) -> String;
+
+    /// The start of `render` that every game shares: who the viewer is playing as, whose turn it is and the clocks
+    fn render_header(&self, game: &Game, viewer_number: Option<u8>) -> String {
+        let mut disp = "\n".to_string();
+        if let Some(number) = viewer_number {
+            disp.push_str(&format!("You are Player {}, playing {}\n", number, Self::player_symbol(number)));
+        } else {
+            disp.push_str("You are spectating\n");
+        }
+        if self.in_progress() {
+            let to_move = self.player_to_move(game);
+            if viewer_number == Some(to_move) {
+                disp.push_str(&format!("It is your turn (Player {}) \n", to_move));
+            } else {
+                disp.push_str(&format!("It is Player {}'s turn \n", to_move));
+            }
+            if let Some(clock) = self.clock().describe() {
+                disp.push_str(&format!("{} ({})\n", clock, game.time_control.describe()));
+            }
+        }
+        disp.push('\n');
+        disp
+    }
+
+    /// An example of each type of move
+    fn describe() -> Vec<Self::MoveType>;
+}
+
+/**
+ * Evaluates `$body` with `$rules` as an alias for the GameRules implementation registered under `$game_type`.
+ * Evaluates to an Err if the game type is unknown so `$body` must also return a Result, with an error type
+ * that a GameError can be converted into.
+ */
+macro_rules! with_game_rules {
+    ($game_type:expr, $rules:ident => $body:expr) => {
+        match $game_type.as_str() {
+            crate::connect_four::GAME_TYPE => {
+                type $rules = crate::connect_four::GameState;
+                $body
+            },
+            crate::checkers::GAME_TYPE => {
+                type $rules = crate::checkers::GameState;
+                $body
+            },
+            unknown => Err(crate::error::GameError::UnknownGameType{game_type: unknown.to_string()}.into()),
+        }
+    };
+}
+
+/*==================================================
+=            Shared validation fragments           =
+==================================================*/
+
+pub fn is_game_in_progress<R: GameRules>(state: &R) -> Result<(), GameError> {
+    if state.in_progress() {
+        Ok(())
+    } else {
+        Err(GameError::GameOver)
+    }
+}
+
+/// Returns the number of the player
+pub fn is_player_in_game(game: &Game, player: &Address) -> Result<u8, GameError> {
+    game.player_number(player).ok_or(GameError::NotAPlayer)
+}
+
+/// Returns the number of the player, who must be the one to move and still have time on their clock
+pub fn is_players_turn_in_time<R: GameRules>(game: &Game, state: &R, next_move: &Move<R::MoveType>) -> Result<u8, GameError> {
+    let player = is_player_in_game(game, &next_move.author)?;
+    if state.player_to_move(game) != player {
+        return Err(GameError::NotYourTurn)
+    }
+    state.clock().is_within_time(player, next_move.timestamp)?;
+    Ok(player)
+}
+
+/// Only the player waiting on their opponent can claim a timeout, once the opponent's clock has run out
+pub fn is_valid_timeout_claim<R: GameRules>(game: &Game, state: &R, claim: &Move<R::MoveType>) -> Result<(), GameError> {
+    let opponent = game::opponent_of(is_player_in_game(game, &claim.author)?);
+    if state.player_to_move(game) != opponent {
+        return Err(GameError::illegal_move("Can only claim a timeout while waiting for your opponent to move"))
+    }
+    state.clock().is_out_of_time(opponent, claim.timestamp)
+}
+
+/*=====  End of Shared validation fragments  ======*/
@@ -1,10 +1,11 @@
-#![feature(try_from, vec_remove_item, proc_macro_hygiene)]
+#![feature(try_from, proc_macro_hygiene)]
 #[macro_use]
 extern crate hdk;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
+extern crate chrono;
 #[macro_use]
 extern crate holochain_json_derive;
 
@@ -18,26 +19,35 @@ use hdk::{
     holochain_persistence_api::{
         cas::content::{Address},
     },
+    holochain_json_api::{
+        json::{JsonString, default_to_json},
+    },
     holochain_core_types::{
         entry::Entry,
     },
 };
+use serde_json::Value as JsonValue;
+
+#[macro_use]
+mod game_rules;
 
-// This is where you would import your own game State, MoveType and state_reducer
+// This is where you would add the module for your own game and register it in `with_game_rules!`
 
 mod connect_four;
-pub use connect_four::{
-    GameState,
-    MoveType,
-};
+mod checkers;
 
 
+mod checkpoint;
+mod clock;
+mod error;
 mod game;
 mod game_move;
 mod matchmaking;
 
-use game::Game;
+use checkpoint::StateCheckpoint;
+use game::{Fork, Game, GameOptions, GameSummary, PlayerGames, Since};
 use game_move::{Move, MoveInput};
+use game_rules::GameRules;
 use matchmaking::{GameProposal, GetResponse};
 
 #[zome]
@@ -62,6 +72,11 @@ pub mod main {
         game_move::definition()
     }
 
+    #[entry_def]
+    fn state_checkpoint_entry_def() -> ValidatingEntryType {
+        checkpoint::definition()
+    }
+
     #[entry_def]
     fn game_proposal_def() -> ValidatingEntryType {
         matchmaking::game_proposal_def()
@@ -80,66 +95,118 @@ pub mod main {
     ======================================*/
 
     #[zome_fn("hc_public")]
-    fn create_game(opponent: Address, timestamp: u32) -> ZomeApiResult<Address> {
-        let new_game = Game {
-            player_1: AGENT_ADDRESS.to_string().into(),
-            player_2: opponent,
-            created_at: timestamp,
-        };
+    fn create_game(opponent: Address, timestamp: u32, options: Option<GameOptions>) -> ZomeApiResult<Address> {
+        let new_game = Game::new(
+            AGENT_ADDRESS.to_string().into(),
+            opponent,
+            timestamp,
+            options.unwrap_or_default(),
+        );
         let game_entry = Entry::App(
             "game".into(),
-            new_game.into(),
+            new_game.clone().into(),
         );
-        hdk::commit_entry(&game_entry)
+        let game_address = hdk::commit_entry(&game_entry)?;
+        game::link_from_players(&game_address, &new_game)?;
+        Ok(game_address)
     }
 
     #[zome_fn("hc_public")]
     fn make_move(new_move: MoveInput) -> ZomeApiResult<()> {
-        // get all the moves from the DHT by following the hash chain
-        let moves = game::get_moves(&new_move.game)?;
-
-        // commit the latest move to local chain to allow validation of the next move (if one exists)
-        let base_address = match moves.last() {
-            Some(last_move) => {
-                let last_move_entry = Entry::App("move".into(), last_move.into());
-                hdk::commit_entry(&last_move_entry)?
-            }
-            None => { // no moves have been made so commit the Game
-                let game = game::get_game(&new_move.game)?;
-                let game_entry = Entry::App("game".into(), game.into());
-                hdk::commit_entry(&game_entry)?
-            }
-        };
+        let game = game::get_game(&new_move.game)?;
+        with_game_rules!(game.game_type, R => game_move::handle_make_move::<R>(game.clone(), new_move))
+    }
 
-        let new_move = Move {
-            game: new_move.game,
-            author: AGENT_ADDRESS.to_string().into(),
-            move_type: new_move.move_type,
-            previous_move: base_address.clone(),
-            timestamp: new_move.timestamp,
-        };
-        let move_entry = Entry::App(
-            "move".into(),
-            new_move.into(),
-        );
-        let move_address = hdk::commit_entry(&move_entry)?;
-        hdk::link_entries(&base_address, &move_address, "", "")?;
-        Ok(())
+    #[zome_fn("hc_public")]
+    fn commit_move(new_move: Move<JsonValue>) -> ZomeApiResult<Address> {
+        game_move::commit_move(new_move)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_state(game_address: Address) -> ZomeApiResult<JsonString> {
+        let game = game::get_game(&game_address)?;
+        with_game_rules!(game.game_type, R => {
+            game::get_state::<R>(&game_address, &game).map(default_to_json)
+        })
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_state_at(game_address: Address, ply: usize) -> ZomeApiResult<JsonString> {
+        let game = game::get_game(&game_address)?;
+        with_game_rules!(game.game_type, R => {
+            game::get_state_at_ply::<R>(&game_address, &game, ply).map(default_to_json)
+        })
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_state_since(game_address: Address, since: Since) -> ZomeApiResult<JsonString> {
+        let game = game::get_game(&game_address)?;
+        with_game_rules!(game.game_type, R => {
+            game::get_state_since::<R>(&game_address, &game, &since).map(default_to_json)
+        })
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_game_summary(game_address: Address) -> ZomeApiResult<GameSummary> {
+        game::get_game_summary(&game_address)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_checkpoints(game_address: Address) -> ZomeApiResult<Vec<StateCheckpoint>> {
+        checkpoint::get_checkpoints(&game_address)
     }
 
     #[zome_fn("hc_public")]
-    fn get_state(game_address: Address) -> ZomeApiResult<GameState> {
-        game::get_state(&game_address)
+    fn get_forks(game_address: Address) -> ZomeApiResult<Vec<Fork>> {
+        let game = game::get_game(&game_address)?;
+        with_game_rules!(game.game_type, R => {
+            game::get_move_chain::<R>(&game_address, &game).map(|chain| chain.forks)
+        })
     }
 
     #[zome_fn("hc_public")]
     fn render_state(game_address: Address) -> ZomeApiResult<String> {
-        Ok(game::get_state(&game_address)?.render())
+        game::render_state(&game_address, Some(&AGENT_ADDRESS.to_string().into()))
+    }
+
+    #[zome_fn("hc_public")]
+    fn render_state_for(game_address: Address, viewer: Option<Address>) -> ZomeApiResult<String> {
+        game::render_state(&game_address, viewer.as_ref())
+    }
+
+    #[zome_fn("hc_public")]
+    fn render_state_at(game_address: Address, ply: usize) -> ZomeApiResult<String> {
+        game::render_state_at_ply(&game_address, ply, Some(&AGENT_ADDRESS.to_string().into()))
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_valid_moves(game_address: Option<Address>) -> ZomeApiResult<JsonString> {
+        let game_type = match game_address {
+            Some(game_address) => game::get_game(&game_address)?.game_type,
+            None => connect_four::GAME_TYPE.to_string(),
+        };
+        with_game_rules!(game_type, R => Ok(default_to_json(R::describe())))
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_legal_moves(game_address: Address, player: Option<Address>, timestamp: u32) -> ZomeApiResult<JsonString> {
+        let game = game::get_game(&game_address)?;
+        let player = player.unwrap_or_else(|| AGENT_ADDRESS.to_string().into());
+        // Moves are checked as if made at `timestamp`, which decides whether either clock has run out
+        with_game_rules!(game.game_type, R => {
+            let state = game::get_state::<R>(&game_address, &game)?;
+            Ok(default_to_json(game_move::legal_moves::<R>(&game_address, &game, &state, &player, timestamp)))
+        })
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_my_games() -> ZomeApiResult<PlayerGames> {
+        game::get_games_of(&AGENT_ADDRESS.to_string().into())
     }
 
     #[zome_fn("hc_public")]
-    fn get_valid_moves() -> ZomeApiResult<Vec<MoveType>> {
-        Ok(MoveType::describe())
+    fn get_games_of(agent: Address) -> ZomeApiResult<PlayerGames> {
+        game::get_games_of(&agent)
     }
 
     #[zome_fn("hc_public")]
@@ -159,8 +226,8 @@ pub mod main {
     }
 
     #[zome_fn("hc_public")]
-    fn accept_proposal(proposal_addr: Address, created_at: u32) -> ZomeApiResult<Address> {
-        matchmaking::handle_accept_proposal(proposal_addr, created_at)
+    fn accept_proposal(proposal_addr: Address, created_at: u32, options: Option<GameOptions>) -> ZomeApiResult<Address> {
+        matchmaking::handle_accept_proposal(proposal_addr, created_at, options.unwrap_or_default())
     }
 
     #[zome_fn("hc_public")]
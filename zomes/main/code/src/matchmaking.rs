@@ -19,7 +19,7 @@ use hdk::{
 use serde::Serialize;
 use std::fmt::Debug;
 
-use crate::game::Game;
+use crate::game::{self, Game, GameOptions};
 
 #[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
 pub struct GameProposal {
@@ -95,21 +95,23 @@ pub fn handle_get_proposals() -> ZomeApiResult<Vec<GetResponse<GameProposal>>> {
     )
 }
 
-pub fn handle_accept_proposal(proposal_addr: Address, created_at: u32) -> ZomeApiResult<Address> {
+pub fn handle_accept_proposal(proposal_addr: Address, created_at: u32, options: GameOptions) -> ZomeApiResult<Address> {
     // this will early return error if it doesn't exist
     let proposal: GameProposal = hdk::utils::get_as_type(proposal_addr.clone())?;
 
     // create the new game
-    let game = Game {
-        player_1: AGENT_ADDRESS.to_string().into(),
-        player_2: proposal.agent,
+    let game = Game::new(
+        AGENT_ADDRESS.to_string().into(),
+        proposal.agent,
         created_at,
-    };
+        options,
+    );
     let game_entry = Entry::App(
         "game".into(),
-        game.into()
+        game.clone().into()
     );
     let game_addr = hdk::commit_entry(&game_entry)?;
+    game::link_from_players(&game_addr, &game)?;
 
     // link to the proposal
     hdk::link_entries(